@@ -0,0 +1,49 @@
+//! Times the expansion of an enum with 500 variants, to catch regressions in the generator. Run it with
+//! `cargo bench --bench expand`.
+//!
+//! A proc-macro crate can only export its macros, so the generator is included here as a plain module.
+
+#[allow(dead_code, unused_imports)]
+#[path = "../src/expand/mod.rs"]
+mod expand;
+
+/// How many times each enum is expanded, after a run to warm up.
+const RUNS: u32 = 20;
+
+/// Builds an enum with `count` unit variants (and one keyed, dense, and multi variant each), with the attributes.
+fn large_enum(count: usize, attrs: proc_macro2::TokenStream) -> syn::ItemEnum {
+    let variants = (0..count).map(|i| quote::format_ident!("V{:03}", i));
+    syn::parse_quote! {
+        #attrs
+        pub enum Large {
+            #(#variants,)*
+            Keyed(u32),
+            #[dense_key(max = 64)] Dense(u8),
+            #[multi] Multi(u16)
+        }
+    }
+}
+
+fn main() {
+    let cases = [
+        ("plain", quote::quote! {}),
+        ("handlers", quote::quote! { #[handlers] #[visitor] }),
+        ("serde_pairs", quote::quote! { #[serde_pairs] #[struct_derive(Debug, Clone, PartialEq)] })
+    ];
+    for (name, attrs) in cases {
+        let input = large_enum(500, attrs);
+        let enum_path = input.ident.clone().into();
+        drop(expand::expand(&input, &enum_path, false));
+        let times: Vec<_> = (0..RUNS).map(|_| {
+            let start = std::time::Instant::now();
+            drop(expand::expand(&input, &enum_path, false));
+            start.elapsed()
+        }).collect();
+        let fastest = times.iter().min().unwrap();
+        let mean = times.iter().sum::<std::time::Duration>() / RUNS;
+        println!(
+            "expand 500 variants ({}): {:.2} ms fastest, {:.2} ms mean",
+            name, fastest.as_secs_f64() * 1e3, mean.as_secs_f64() * 1e3
+        );
+    }
+}
@@ -0,0 +1,766 @@
+//! Reading the attributes of the enum.
+
+use syn::{Ident, ItemEnum, spanned::Spanned, parse::{Parse, ParseStream}, ext::IdentExt};
+use quote::{quote, format_ident};
+use inflector::Inflector;
+use proc_macro_error::emit_error;
+use check_keyword::CheckKeyword;
+
+/// The attributes of the enum, read by `EnumAttrs::read`.
+pub(super) struct EnumAttrs {
+    pub(super) struct_ident: Ident,
+    pub(super) bounds: Vec<syn::TypeParamBound>,
+    pub(super) derives: Vec<syn::Path>,
+    pub(super) catch_all: Option<Ident>,
+    pub(super) auto_insert: bool,
+    pub(super) tables: Vec<Table>,
+    /// Each merged enum, with the path of its variants struct.
+    pub(super) merge_with: Vec<(syn::Path, syn::Path)>,
+    pub(super) markers: Option<Ident>,
+    pub(super) cell: Option<Ident>,
+    /// The `handlers` attribute, with the name given to it (if any).
+    pub(super) handlers: Option<(proc_macro2::Span, Option<Ident>)>,
+    pub(super) jump_table: Option<syn::Path>,
+    pub(super) preserve_case: Option<syn::Path>,
+    pub(super) keep_lints: bool,
+    pub(super) into_args: bool,
+    pub(super) serde_skip_maps: Option<syn::Path>,
+    pub(super) serde_pairs: Option<syn::Path>,
+    pub(super) cross_eq: Option<syn::Path>,
+    /// The path of `rand`.
+    pub(super) random: Option<syn::Path>,
+    pub(super) visitor: bool,
+    pub(super) for_each_macro: Option<Ident>,
+    pub(super) getter_table: bool,
+    pub(super) repr_helpers: Option<syn::Path>,
+    pub(super) no_alloc: Option<syn::Path>,
+    pub(super) boxed_fields: Option<syn::Path>,
+    pub(super) ordered_maps: bool,
+    /// The `async_locks` attribute, whether it asks for `Mutex`es instead of `RwLock`s, and the path of `tokio`.
+    pub(super) async_locks: Option<(syn::Path, bool, syn::Path)>,
+    /// The concrete value types that get extra impl blocks.
+    pub(super) impl_fors: Vec<ImplFor>,
+    /// Attributes that are put on each of the generated impl blocks.
+    pub(super) impl_attrs: Vec<syn::Meta>,
+    /// Whether the struct and the helper types (like the iterators) are hidden from the docs.
+    pub(super) hide_struct: bool,
+    pub(super) hide_helpers: bool,
+    pub(super) rename_fields: RenameFields,
+    /// The fields are sorted by name with `field_order(alphabetical)`, by discriminant with `field_order(discriminant)`,
+    /// or in the order of the variants given to `field_order`.
+    pub(super) alphabetical: bool,
+    pub(super) by_discriminant: bool,
+    pub(super) field_order: Option<Vec<Ident>>,
+    /// The primitive `repr` of the enum, if it has one.
+    pub(super) repr: Option<Ident>
+}
+
+impl EnumAttrs {
+    /// Reads the `struct_bounds`, `struct_derive`, `struct_name`, `catch_all`, `auto_insert`, `jump_table`,
+    /// `preserve_case`, `rename_fields`, `struct_doc`, `keep_lints`, `into_args`, `serde_skip_maps`, `serde_pairs`,
+    /// `markers`, `merge_with`, `table`, `field_order`, `cell`, `cross_eq`, `random`, `no_alloc`, `boxed_fields`,
+    /// `impl_attr`, `ordered_maps`, `async_locks`, `impl_for`, `handlers`, `visitor`, `for_each_macro`, `getter_table`,
+    /// and `repr_helpers` attributes, and the `repr`. (ignoring any others)
+    pub(super) fn read(input: &ItemEnum) -> EnumAttrs {
+        let enum_ident = &input.ident;
+        let mut struct_ident = format_ident!("{}Struct", input.ident);
+        let mut bounds = vec![];
+        let mut derives = vec![];
+        let mut catch_all = None;
+        let mut auto_insert = false;
+        let mut tables = vec![];
+        let mut merge_with = vec![];
+        let mut markers = None;
+        let mut cell = None;
+        let mut handlers = None;
+        let mut jump_table = None;
+        let mut preserve_case = None;
+        let mut keep_lints = false;
+        let mut into_args = false;
+        let mut serde_skip_maps = None;
+        let mut serde_pairs = None;
+        let mut cross_eq = None;
+        let mut random = None;
+        let mut visitor = false;
+        let mut for_each_macro = None;
+        let mut getter_table = false;
+        let mut repr_helpers = None;
+        let mut no_alloc = None;
+        let mut boxed_fields = None;
+        let mut ordered_maps = false;
+        let mut async_locks = None;
+        let mut impl_fors = vec![];
+        let mut impl_attrs = vec![];
+        let mut hide_struct = false;
+        let mut hide_helpers = false;
+        let mut rename_fields = RenameFields::default();
+        let mut alphabetical = false;
+        let mut by_discriminant = false;
+        let mut field_order = None;
+        for attr in &input.attrs {
+            let attr_name = match attr.path.get_ident() {
+                Some(ident) => ident.to_string(),
+                None => continue
+            };
+            match attr_name.as_str() {
+                "struct_bounds" => match attr.parse_args_with(parse_bounds) {
+                    Ok(parsed) => {
+                        for bound in parsed {
+                            match &bound {
+                                syn::TypeParamBound::Trait(syn::TraitBound {modifier: syn::TraitBoundModifier::Maybe(_), ..}) => emit_error!(
+                                    bound, "`T` can't be `?Sized`, since the struct stores its values by value"
+                                ),
+                                syn::TypeParamBound::Lifetime(lifetime) if lifetime.ident != "static" => emit_error!(
+                                    lifetime, "`{}` isn't declared, since the struct only has the type argument `T`", lifetime;
+                                    help = "`'static` is the only lifetime that can be used here"
+                                ),
+                                _ => bounds.push(rename_bound_lifetimes(bound))
+                            }
+                        }
+                    }
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "struct_derive" => match attr.parse_meta() {
+                    Ok(syn::Meta::List(syn::MetaList {nested, ..})) => {
+                        for meta in nested {
+                            match meta {
+                                syn::NestedMeta::Meta(syn::Meta::Path(path)) => derives.push(path),
+                                _ => emit_error!(meta, "only path arguments are accepted")
+                            }
+                        }
+                    }
+                    Ok(meta) => emit_error!(meta, "expected a list, like `struct_derive(...)`"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "struct_name" => match attr.parse_meta() {
+                    Ok(syn::Meta::NameValue(syn::MetaNameValue {lit, ..})) => {
+                        if let Some(ident) = parse_ident_lit(&lit, false) {
+                            struct_ident = ident;
+                        }
+                    }
+                    Ok(meta) => emit_error!(meta, "expected a name, like `struct_name = \"...\"`"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "catch_all" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(path)) => {
+                        catch_all = Some(format_ident!("catch_all", span = path.span()));
+                    }
+                    Ok(syn::Meta::NameValue(syn::MetaNameValue {lit, ..})) => {
+                        if let Some(ident) = parse_ident_lit(&lit, true) {
+                            catch_all = Some(ident);
+                        }
+                    }
+                    Ok(meta) => emit_error!(meta, "expected `catch_all` or `catch_all = \"...\"`"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "auto_insert" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(_)) => auto_insert = true,
+                    Ok(meta) => emit_error!(meta, "`auto_insert` doesn't take any arguments"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "preserve_case" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(path)) => preserve_case = Some(path),
+                    Ok(meta) => emit_error!(meta, "`preserve_case` doesn't take any arguments"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "struct_doc" => match attr.parse_meta() {
+                    Ok(syn::Meta::List(syn::MetaList {nested, ..})) => for meta in nested {
+                        match meta {
+                            syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("hidden") => {
+                                hide_struct = true;
+                                hide_helpers = true;
+                            }
+                            syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("hidden_helpers") => hide_helpers = true,
+                            _ => emit_error!(meta, "expected one of `hidden` or `hidden_helpers`")
+                        }
+                    }
+                    Ok(meta) => emit_error!(meta, "expected a list, like `struct_doc(hidden)`"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "serde_skip_maps" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(path)) => serde_skip_maps = Some(path),
+                    Ok(meta) => emit_error!(meta, "`serde_skip_maps` doesn't take any arguments"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "serde_pairs" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(path)) => serde_pairs = Some(path),
+                    Ok(meta) => emit_error!(meta, "`serde_pairs` doesn't take any arguments"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "cross_eq" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(path)) => cross_eq = Some(path),
+                    Ok(meta) => emit_error!(meta, "`cross_eq` doesn't take any arguments"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "for_each_macro" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(path)) => for_each_macro = Some(format_ident!(
+                        "for_each_{}_variant", enum_ident.to_string().to_snake_case(), span = path.span()
+                    )),
+                    Ok(syn::Meta::NameValue(syn::MetaNameValue {lit, ..})) => {
+                        if let Some(ident) = parse_ident_lit(&lit, false) {
+                            for_each_macro = Some(ident);
+                        }
+                    }
+                    Ok(meta) => emit_error!(meta, "expected `for_each_macro` or `for_each_macro = \"...\"`"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "visitor" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(_)) => visitor = true,
+                    Ok(meta) => emit_error!(meta, "`visitor` doesn't take any arguments"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "repr_helpers" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(path)) => repr_helpers = Some(path),
+                    Ok(meta) => emit_error!(meta, "`repr_helpers` doesn't take any arguments"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "getter_table" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(_)) => getter_table = true,
+                    Ok(meta) => emit_error!(meta, "`getter_table` doesn't take any arguments"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "random" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(path)) => random = Some(syn::Path::from(Ident::new("rand", path.span()))),
+                    Ok(syn::Meta::List(syn::MetaList {nested, ..})) => match nested.iter().collect::<Vec<_>>().as_slice() {
+                        [syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {path, lit, ..}))] if path.is_ident("crate") => {
+                            random = parse_path_lit(lit);
+                        }
+                        _ => emit_error!(nested, "expected the path of `rand`, like `random(crate = \"rand\")`")
+                    }
+                    Ok(meta) => emit_error!(meta, "expected `random` or `random(crate = \"...\")`"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "impl_attr" => match attr.parse_meta() {
+                    Ok(syn::Meta::List(syn::MetaList {nested, ..})) => {
+                        for meta in nested {
+                            match meta {
+                                syn::NestedMeta::Meta(meta) => impl_attrs.push(meta),
+                                _ => emit_error!(meta, "expected an attribute, like `impl_attr(cfg(feature = \"...\"))`")
+                            }
+                        }
+                    }
+                    Ok(meta) => emit_error!(meta, "expected a list, like `impl_attr(...)`"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "impl_for" => match attr.parse_args::<ImplFor>() {
+                    Ok(impl_for) => {
+                        for extra in &impl_for.extras {
+                            if !IMPL_FOR_EXTRAS.contains(&extra.to_string().as_str()) {
+                                emit_error!(
+                                    extra, "unknown extra `{}`", extra;
+                                    help = "the extras are {}", IMPL_FOR_EXTRAS.iter().map(|name| format!("`{}`", name)).collect::<Vec<_>>().join(", ")
+                                );
+                            }
+                        }
+                        impl_fors.push(impl_for);
+                    }
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "async_locks" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(path)) => async_locks = Some((path.clone(), false, syn::Path::from(Ident::new("tokio", path.span())))),
+                    Ok(syn::Meta::List(syn::MetaList {path, nested, ..})) => {
+                        let mut mutex = false;
+                        let mut tokio = syn::Path::from(Ident::new("tokio", path.span()));
+                        for meta in &nested {
+                            match meta {
+                                syn::NestedMeta::Meta(syn::Meta::Path(kind)) if kind.is_ident("mutex") => mutex = true,
+                                syn::NestedMeta::Meta(syn::Meta::Path(kind)) if kind.is_ident("rw_lock") => mutex = false,
+                                syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {path, lit, ..})) if path.is_ident("crate") => {
+                                    if let Some(path) = parse_path_lit(lit) {
+                                        tokio = path;
+                                    }
+                                }
+                                _ => emit_error!(meta, "expected `mutex`, `rw_lock`, or `crate = \"...\"`")
+                            }
+                        }
+                        async_locks = Some((path, mutex, tokio));
+                    }
+                    Ok(meta) => emit_error!(meta, "expected `async_locks`, or `async_locks(...)` with `mutex`, `rw_lock`, or `crate = \"...\"`"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "ordered_maps" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(_)) => ordered_maps = true,
+                    Ok(meta) => emit_error!(meta, "`ordered_maps` doesn't take any arguments"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "boxed_fields" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(path)) => boxed_fields = Some(path),
+                    Ok(meta) => emit_error!(meta, "`boxed_fields` doesn't take any arguments"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "no_alloc" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(path)) => no_alloc = Some(path),
+                    Ok(meta) => emit_error!(meta, "`no_alloc` doesn't take any arguments"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "into_args" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(_)) => into_args = true,
+                    Ok(meta) => emit_error!(meta, "`into_args` doesn't take any arguments"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "keep_lints" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(_)) => keep_lints = true,
+                    Ok(meta) => emit_error!(meta, "`keep_lints` doesn't take any arguments"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "rename_fields" => match attr.parse_meta() {
+                    Ok(meta) => rename_fields.parse(meta),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "jump_table" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(path)) => jump_table = Some(path),
+                    Ok(meta) => emit_error!(meta, "`jump_table` doesn't take any arguments"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "markers" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(path)) => {
+                        let name = enum_ident.to_string().to_snake_case();
+                        markers = Some(format_ident!("{}", name.into_safe(), span = path.span()));
+                    }
+                    Ok(syn::Meta::NameValue(syn::MetaNameValue {lit, ..})) => {
+                        if let Some(ident) = parse_ident_lit(&lit, true) {
+                            markers = Some(ident);
+                        }
+                    }
+                    Ok(meta) => emit_error!(meta, "expected `markers` or `markers = \"...\"`"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "cell" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(path)) => cell = Some(format_ident!("{}Cell", struct_ident, span = path.span())),
+                    Ok(syn::Meta::NameValue(syn::MetaNameValue {lit, ..})) => {
+                        if let Some(ident) = parse_ident_lit(&lit, false) {
+                            cell = Some(ident);
+                        }
+                    }
+                    Ok(meta) => emit_error!(meta, "expected `cell` or `cell = \"...\"`"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "handlers" => match attr.parse_meta() {
+                    Ok(syn::Meta::Path(path)) => handlers = Some((path.span(), None)),
+                    Ok(syn::Meta::NameValue(syn::MetaNameValue {path, lit, ..})) => {
+                        if let Some(ident) = parse_ident_lit(&lit, false) {
+                            handlers = Some((path.span(), Some(ident)));
+                        }
+                    }
+                    Ok(meta) => emit_error!(meta, "expected `handlers` or `handlers = \"...\"`"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "merge_with" => match attr.parse_meta() {
+                    Ok(syn::Meta::List(syn::MetaList {nested, ..})) => {
+                        for meta in nested {
+                            match meta {
+                                syn::NestedMeta::Meta(syn::Meta::Path(path)) => {
+                                    let merged_struct = default_struct_path(&path);
+                                    merge_with.push((path, merged_struct));
+                                }
+                                syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {path, lit, ..})) => {
+                                    if let Some(merged_struct) = parse_path_lit(&lit) {
+                                        merge_with.push((path, merged_struct));
+                                    }
+                                }
+                                _ => emit_error!(meta, "expected an enum, like `Other` or `Other = \"OtherStruct\"`")
+                            }
+                        }
+                    }
+                    Ok(meta) => emit_error!(meta, "expected a list, like `merge_with(...)`"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "table" => match attr.parse_meta() {
+                    Ok(syn::Meta::List(syn::MetaList {nested, ..})) => {
+                        tables.extend(parse_table(nested, attr.span(), enum_ident));
+                    }
+                    Ok(meta) => emit_error!(meta, "expected a list, like `table(cols = \"...\")`"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                "field_order" => match attr.parse_meta() {
+                    Ok(syn::Meta::List(syn::MetaList {nested, ..})) => {
+                        let mut idents = vec![];
+                        for meta in nested {
+                            match meta {
+                                syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.get_ident().is_some() => {
+                                    idents.push(path.get_ident().unwrap().clone());
+                                }
+                                _ => emit_error!(meta, "expected `declaration`, `alphabetical`, `discriminant`, or the variants in order")
+                            }
+                        }
+                        match idents.as_slice() {
+                            [mode] if mode == "alphabetical" => alphabetical = true,
+                            [mode] if mode == "discriminant" => by_discriminant = true,
+                            [mode] if mode == "declaration" => {}
+                            _ => field_order = Some(idents)
+                        }
+                    }
+                    Ok(meta) => emit_error!(meta, "expected a list, like `field_order(alphabetical)` or `field_order(...)`"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+                _ => {}
+            }
+        }
+
+        let variant_names: Vec<_> = input.variants.iter().map(|var| var.ident.unraw().to_string()).collect();
+        rename_fields.detect_prefix(&variant_names);
+        if let (Some(path), Some(_)) = (&preserve_case, rename_fields.case) {
+            emit_error!(path, "`preserve_case` can't be used with a case from `rename_fields`");
+        }
+
+        // a primitive `repr` on a fieldless enum gives every variant a stable integer value to convert to and from
+        let repr = input.attrs.iter()
+            .filter(|attr| attr.path.is_ident("repr"))
+            .filter_map(|attr| match attr.parse_meta() {
+                Ok(syn::Meta::List(syn::MetaList {nested, ..})) => Some(nested),
+                _ => None
+            })
+            .flatten()
+            .find_map(|meta| match meta {
+                syn::NestedMeta::Meta(syn::Meta::Path(path)) => path.get_ident()
+                    .filter(|ident| INT_REPRS.iter().any(|int| *ident == int))
+                    .cloned(),
+                _ => None
+            });
+
+        EnumAttrs {
+            struct_ident,
+            bounds,
+            derives,
+            catch_all,
+            auto_insert,
+            tables,
+            merge_with,
+            markers,
+            cell,
+            handlers,
+            jump_table,
+            preserve_case,
+            keep_lints,
+            into_args,
+            serde_skip_maps,
+            serde_pairs,
+            cross_eq,
+            random,
+            visitor,
+            for_each_macro,
+            getter_table,
+            repr_helpers,
+            no_alloc,
+            boxed_fields,
+            ordered_maps,
+            async_locks,
+            impl_fors,
+            impl_attrs,
+            hide_struct,
+            hide_helpers,
+            rename_fields,
+            alphabetical,
+            by_discriminant,
+            field_order,
+            repr
+        }
+    }
+}
+
+/// The integer types that can be given to `#[repr(...)]`.
+const INT_REPRS: &[&str] = &["u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize"];
+
+/// Renames the lifetimes declared by a higher-ranked bound (like the `'a` in `for<'a> Fn(&'a T)`), so that they don't
+/// shadow the lifetimes of the generated impls.
+fn rename_bound_lifetimes(bound: syn::TypeParamBound) -> syn::TypeParamBound {
+    fn rename(tokens: proc_macro2::TokenStream, names: &[String]) -> proc_macro2::TokenStream {
+        let mut renamed = vec![];
+        let mut after_quote = false;
+        for token in tokens {
+            let is_quote = matches!(&token, proc_macro2::TokenTree::Punct(punct) if punct.as_char() == '\'');
+            renamed.push(match token {
+                proc_macro2::TokenTree::Ident(ident) if after_quote && names.contains(&ident.to_string()) => {
+                    proc_macro2::TokenTree::Ident(format_ident!("__bound_{}", ident))
+                }
+                proc_macro2::TokenTree::Group(group) => {
+                    let mut renamed_group = proc_macro2::Group::new(group.delimiter(), rename(group.stream(), names));
+                    renamed_group.set_span(group.span());
+                    proc_macro2::TokenTree::Group(renamed_group)
+                }
+                token => token
+            });
+            after_quote = is_quote;
+        }
+        renamed.into_iter().collect()
+    }
+
+    match &bound {
+        syn::TypeParamBound::Trait(syn::TraitBound {lifetimes: Some(lifetimes), ..}) => {
+            let names: Vec<_> = lifetimes.lifetimes.iter().map(|def| def.lifetime.ident.to_string()).collect();
+            syn::parse2(rename(quote! { #bound }, &names)).unwrap_or(bound)
+        }
+        _ => bound
+    }
+}
+
+/// Parses the arguments of `struct_bounds`, which are bounds on `T` separated by `,` or `+`.
+fn parse_bounds(input: ParseStream) -> syn::Result<Vec<syn::TypeParamBound>> {
+    let mut bounds = vec![];
+    while !input.is_empty() {
+        bounds.push(input.parse()?);
+        if input.is_empty() {
+            break;
+        }
+        let lookahead = input.lookahead1();
+        if lookahead.peek(syn::Token![,]) {
+            input.parse::<syn::Token![,]>()?;
+        } else if lookahead.peek(syn::Token![+]) {
+            input.parse::<syn::Token![+]>()?;
+        } else {
+            return Err(lookahead.error());
+        }
+    }
+    Ok(bounds)
+}
+
+/// Parses the value of a `name = "value"` attribute into a path, like `parse_ident_lit`.
+fn parse_path_lit(lit: &syn::Lit) -> Option<syn::Path> {
+    match lit {
+        syn::Lit::Str(lit_str) => match lit_str.parse::<syn::Path>() {
+            Ok(path) => Some(path),
+            Err(_) => {
+                emit_error!(lit_str, "`{}` is not a valid path", lit_str.value());
+                None
+            }
+        }
+        _ => {
+            emit_error!(lit, "must be a str literal");
+            None
+        }
+    }
+}
+
+/// Gives the path of the variants struct of another enum, assuming that it wasn't renamed.
+fn default_struct_path(enum_path: &syn::Path) -> syn::Path {
+    let mut path = enum_path.clone();
+    let last = path.segments.last_mut().unwrap();
+    last.ident = format_ident!("{}Struct", last.ident, span = last.ident.span());
+    path
+}
+
+/// A two-dimensional table requested with `#[table(cols = "...")]`.
+pub(super) struct Table {
+    /// The enum of the columns.
+    pub(super) cols: syn::Path,
+    /// The variants struct of the columns.
+    pub(super) cols_struct: syn::Path,
+    pub(super) name: Ident
+}
+
+/// Parses the arguments of a `table` attribute.
+///
+/// `cols_struct` defaults to the default struct name of `cols`, and `name` defaults to `{Enum}{Cols}Table`.
+fn parse_table(nested: syn::punctuated::Punctuated<syn::NestedMeta, syn::Token![,]>, span: proc_macro2::Span, enum_ident: &Ident) -> Option<Table> {
+    let mut cols = None;
+    let mut cols_struct = None;
+    let mut name = None;
+    for meta in nested {
+        match meta {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {path, lit, ..})) => {
+                if path.is_ident("cols") {
+                    cols = parse_path_lit(&lit);
+                } else if path.is_ident("cols_struct") {
+                    cols_struct = parse_path_lit(&lit);
+                } else if path.is_ident("name") {
+                    name = parse_ident_lit(&lit, false);
+                } else {
+                    emit_error!(path, "expected `cols`, `cols_struct`, or `name`");
+                }
+            }
+            _ => emit_error!(meta, "expected an argument like `cols = \"...\"`")
+        }
+    }
+    let cols = match cols {
+        Some(cols) => cols,
+        None => {
+            emit_error!(span, "tables need an enum for their columns, like `table(cols = \"...\")`");
+            return None;
+        }
+    };
+    let cols_ident = &cols.segments.last().unwrap().ident;
+    let cols_struct = cols_struct.unwrap_or_else(|| default_struct_path(&cols));
+    let name = name.unwrap_or_else(|| format_ident!("{}{}Table", enum_ident, cols_ident));
+    Some(Table { cols, cols_struct, name })
+}
+
+/// Parses the value of a `name = "value"` attribute into an identifier.
+///
+/// The identifier is given the span of the string literal, so that errors involving it point at the attribute.
+/// If the literal isn't a valid identifier, an error is emitted and `None` is returned.
+pub(crate) fn parse_ident_lit(lit: &syn::Lit, make_safe: bool) -> Option<Ident> {
+    match lit {
+        syn::Lit::Str(lit_str) => {
+            let value = if make_safe { lit_str.value().into_safe() } else { lit_str.value() };
+            match syn::parse_str::<Ident>(&value) {
+                Ok(mut ident) => {
+                    ident.set_span(lit_str.span());
+                    Some(ident)
+                }
+                Err(_) => {
+                    emit_error!(lit_str, "`{}` is not a valid identifier", lit_str.value());
+                    None
+                }
+            }
+        }
+        _ => {
+            emit_error!(lit, "must be a str literal");
+            None
+        }
+    }
+}
+
+/// The name of a case, and the conversion to it.
+type FieldCase = (&'static str, fn(&str) -> String);
+
+/// The cases that `rename_fields` can convert the variant names to.
+const FIELD_CASES: &[FieldCase] = &[
+    ("snake_case", |name| name.to_snake_case()),
+    ("SCREAMING_SNAKE_CASE", |name| name.to_screaming_snake_case()),
+    ("camelCase", |name| name.to_camel_case()),
+    ("PascalCase", |name| name.to_pascal_case()),
+    ("lowercase", |name| name.to_lowercase()),
+    ("UPPERCASE", |name| name.to_uppercase())
+];
+
+/// The ways that `rename_fields` changes the field names.
+#[derive(Default)]
+pub(super) struct RenameFields {
+    /// Snake case if not given.
+    case: Option<FieldCase>,
+    prefix: String,
+    suffix: String,
+    /// The prefix to strip from the variant names, which is detected from the variants if `Some(None)`.
+    strip_prefix: Option<Option<String>>
+}
+
+impl RenameFields {
+    /// Parses one of `rename_fields = "case"` or
+    /// `rename_fields(case = "...", prefix = "...", suffix = "...", strip_prefix = "..." | strip_prefix)`.
+    fn parse(&mut self, meta: syn::Meta) {
+        match meta {
+            syn::Meta::NameValue(syn::MetaNameValue {lit, ..}) => self.parse_case(&lit),
+            syn::Meta::List(syn::MetaList {nested, ..}) => for meta in nested {
+                match meta {
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {path, lit, ..})) => {
+                        if path.is_ident("case") {
+                            self.parse_case(&lit);
+                        } else if path.is_ident("prefix") {
+                            self.prefix = parse_name_part(&lit, true);
+                        } else if path.is_ident("suffix") {
+                            self.suffix = parse_name_part(&lit, false);
+                        } else if path.is_ident("strip_prefix") {
+                            self.strip_prefix = Some(Some(parse_name_part(&lit, true)));
+                        } else {
+                            emit_error!(path, "expected one of `case`, `prefix`, `suffix`, or `strip_prefix`");
+                        }
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("strip_prefix") => self.strip_prefix = Some(None),
+                    _ => emit_error!(meta, "expected a name and value, like `prefix = \"...\"`")
+                }
+            }
+            syn::Meta::Path(path) => emit_error!(path, "expected a case, like `rename_fields = \"SCREAMING_SNAKE_CASE\"`")
+        }
+    }
+
+    fn parse_case(&mut self, lit: &syn::Lit) {
+        match lit {
+            syn::Lit::Str(lit_str) => match FIELD_CASES.iter().find(|(name, _)| *name == lit_str.value()) {
+                Some(case) => self.case = Some(*case),
+                None => {
+                    let names: Vec<_> = FIELD_CASES.iter().map(|(name, _)| format!("`{}`", name)).collect();
+                    emit_error!(lit_str, "unknown case, expected one of {}", names.join(", "));
+                }
+            }
+            _ => emit_error!(lit, "must be a str literal")
+        }
+    }
+
+    /// Detects the prefix to strip if it wasn't given, which is the longest prefix shared by all of the variants that
+    /// ends right before an uppercase letter in each of them.
+    fn detect_prefix(&mut self, variants: &[String]) {
+        if let Some(None) = self.strip_prefix {
+            let mut len = match variants {
+                [first, _, ..] => variants.iter().map(|name| {
+                    first.char_indices().zip(name.chars())
+                        .find(|((_, a), b)| a != b)
+                        .map_or(first.len().min(name.len()), |((i, _), _)| i)
+                }).min().unwrap_or(0),
+                _ => 0
+            };
+            while len > 0 && !variants.iter().all(|name| name[len..].starts_with(char::is_uppercase)) {
+                len = variants[0][..len].char_indices().last().map_or(0, |(i, _)| i);
+            }
+            self.strip_prefix = Some(Some(variants.first().map_or(String::new(), |name| name[..len].to_string())));
+        }
+    }
+
+    /// Renames a variant with the case conversion, unless the case is preserved.
+    pub(super) fn rename(&self, variant: &Ident, preserve_case: bool) -> Ident {
+        let name = variant.unraw().to_string();
+        // the prefix is only stripped if what's left can still start an identifier
+        let name = match &self.strip_prefix {
+            Some(Some(prefix)) => match name.strip_prefix(prefix.as_str()) {
+                Some(rest) if rest.starts_with(|c: char| c.is_alphabetic() || c == '_') => rest.to_string(),
+                _ => name
+            }
+            _ => name
+        };
+        let name = match self.case {
+            _ if preserve_case => name,
+            Some((_, convert)) => convert(&name),
+            None => name.to_snake_case()
+        };
+        let name = format!("{}{}{}", self.prefix, name, self.suffix);
+        format_ident!("{}", name.into_safe(), span = variant.span())
+    }
+}
+
+/// Parses a prefix or suffix of an identifier, which can't start with a digit if it's a prefix.
+fn parse_name_part(lit: &syn::Lit, is_prefix: bool) -> String {
+    match lit {
+        syn::Lit::Str(lit_str) => {
+            let value = lit_str.value();
+            let starts_with_digit = value.starts_with(|c: char| c.is_ascii_digit());
+            if value.chars().all(|c| c.is_alphanumeric() || c == '_') && !(is_prefix && starts_with_digit) {
+                value
+            } else {
+                emit_error!(lit_str, "`{}` can't be part of an identifier", value);
+                String::new()
+            }
+        }
+        _ => {
+            emit_error!(lit, "must be a str literal");
+            String::new()
+        }
+    }
+}
+
+/// The extra methods that `impl_for` can generate for a concrete value type.
+const IMPL_FOR_EXTRAS: &[&str] = &["sum", "product", "min", "max", "lerp"];
+
+/// The arguments of an `impl_for` attribute, which are a value type and the extra methods to generate for it.
+pub(super) struct ImplFor {
+    pub(super) ty: syn::Type,
+    /// The extras given with `extras(...)`, or all of them if it's left out.
+    pub(super) extras: Vec<Ident>
+}
+
+impl Parse for ImplFor {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ty: syn::Type = input.parse()?;
+        let mut extras = None;
+        if input.parse::<Option<syn::Token![,]>>()?.is_some() && !input.is_empty() {
+            let keyword: Ident = input.parse()?;
+            if keyword != "extras" {
+                return Err(syn::Error::new(keyword.span(), "expected `extras(...)`"));
+            }
+            let content;
+            syn::parenthesized!(content in input);
+            extras = Some(content.parse_terminated::<_, syn::Token![,]>(Ident::parse)?.into_iter().collect());
+            input.parse::<Option<syn::Token![,]>>()?;
+        }
+        let extras = extras.unwrap_or_else(|| IMPL_FOR_EXTRAS.iter().map(|name| Ident::new(name, ty.span())).collect());
+        Ok(ImplFor { ty, extras })
+    }
+}
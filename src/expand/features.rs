@@ -0,0 +1,827 @@
+//! The attributes that generate more items for the struct, each with its own generator, which generates nothing if its
+//! attribute isn't given.
+
+use syn::{Fields, ext::IdentExt, spanned::Spanned};
+use quote::{quote, format_ident};
+use proc_macro_error::emit_error;
+
+use super::attrs::{ImplFor, Table};
+use super::{storage, Context, EnumAttrs, Field, Merged, Storage};
+
+/// With `repr_helpers`, the integer values of a primitive `repr` on a fieldless enum can be converted to and from.
+pub(super) fn repr_helpers(cx: &Context) -> Option<proc_macro2::TokenStream> {
+    let path = cx.attrs.repr_helpers.as_ref()?;
+    let Context { input, enum_path, visibility, impl_attrs, .. } = cx;
+    match &cx.attrs.repr {
+        None => {
+            emit_error!(path, "`repr_helpers` needs a primitive representation, like `#[repr(u8)]`");
+            None
+        }
+        Some(_) if !cx.is_local => {
+            emit_error!(path, "`repr_helpers` can only be used on enums defined in this crate");
+            None
+        }
+        Some(_) if !input.variants.iter().all(|var| matches!(var.fields, Fields::Unit)) => {
+            emit_error!(path, "`repr_helpers` can only be used on enums with only unit variants");
+            None
+        }
+        Some(repr) => {
+            let normals: Vec<_> = input.variants.iter().map(|var| &var.ident).collect();
+            Some(quote! {
+                #impl_attrs
+                impl #enum_path {
+                    #visibility fn as_repr(&self) -> #repr {
+                        match self {
+                            #(#enum_path::#normals => #enum_path::#normals as #repr),*
+                        }
+                    }
+
+                    #visibility fn from_repr(repr: #repr) -> Option<#enum_path> {
+                        match repr {
+                            #(r if r == #enum_path::#normals as #repr => Some(#enum_path::#normals),)*
+                            _ => None
+                        }
+                    }
+                }
+            })
+        }
+    }
+}
+
+/// With `variants_struct`, the enum gets constructors for its struct.
+pub(super) fn enum_constructors(cx: &Context) -> Option<proc_macro2::TokenStream> {
+    let Context { enum_path, visibility, value_idents, prefill_bounds, allow_args, case_lint, impl_attrs, .. } = cx;
+    let EnumAttrs { struct_ident, bounds, .. } = cx.attrs;
+    let new_args = storage::new_args(cx);
+    let merged_idents = cx.merged_idents();
+    let merged_tys = cx.merged_tys();
+    Some(quote! {
+        #allow_args
+        #case_lint
+        #impl_attrs
+        impl #enum_path {
+            #visibility fn table<T: #(#bounds)+*>(#(#new_args),*) -> #struct_ident<T> where #(#prefill_bounds),* {
+                #struct_ident::new(#(#value_idents,)* #(#merged_idents),*)
+            }
+
+            #visibility fn table_default<T: Default #(+ #bounds)*>() -> #struct_ident<T> where #(#merged_tys: Default),* {
+                #(let #value_idents = T::default();)*
+                #(let #merged_idents = Default::default();)*
+                #struct_ident::new(#(#value_idents,)* #(#merged_idents),*)
+            }
+        }
+    })
+}
+
+/// A struct of only unit fields can be converted between columns (a struct of `Vec`s) and rows (a `Vec` of structs).
+pub(super) fn rows(cx: &Context) -> Option<proc_macro2::TokenStream> {
+    let Context { value_idents, map_idents, dense_idents, multi_idents, deref, case_lint, derived, impl_attrs, .. } = cx;
+    let EnumAttrs { struct_ident, bounds, .. } = cx.attrs;
+    let boxed_fields = cx.boxed_fields;
+    if cx.jump_table || value_idents.is_empty() || !map_idents.is_empty() || !dense_idents.is_empty() || !multi_idents.is_empty()
+        || !cx.merged.is_empty() {
+        return None;
+    }
+    let (row_values, empty_columns) = if boxed_fields {
+        (
+            quote! { #(#value_idents: Box::new(#value_idents.next().unwrap())),* },
+            quote! { #(#value_idents: Box::new(Vec::new())),* }
+        )
+    } else {
+        (
+            quote! { #(#value_idents: #value_idents.next().unwrap()),* },
+            quote! { #(#value_idents: Vec::new()),* }
+        )
+    };
+    Some(quote! {
+        #case_lint
+        #impl_attrs
+        impl<T: #(#bounds)+*> #struct_ident<Vec<T>> where Vec<T>: #(#bounds)+* {
+            pub fn into_rows(self) -> Vec<#struct_ident<T>> {
+                let len = IntoIterator::into_iter([#(self.#value_idents.len()),*]).min().unwrap_or(0);
+                #(let mut #value_idents = (#deref self.#value_idents).into_iter();)*
+                (0..len).map(|_| #struct_ident { #row_values }).collect()
+            }
+        }
+
+        #derived
+        #impl_attrs
+        impl<T: #(#bounds)+*> std::iter::FromIterator<#struct_ident<T>> for #struct_ident<Vec<T>> where Vec<T>: #(#bounds)+* {
+            fn from_iter<I: IntoIterator<Item = #struct_ident<T>>>(rows: I) -> Self {
+                let mut columns = #struct_ident { #empty_columns };
+                for row in rows {
+                    #(columns.#value_idents.push(#deref row.#value_idents);)*
+                }
+                columns
+            }
+        }
+    })
+}
+
+/// With `getter_table`, unit fields can be reached through function pointers, and variants can be classified ahead of
+/// time. These go in the main impl of the struct.
+pub(super) fn getter_table(cx: &Context) -> proc_macro2::TokenStream {
+    if !cx.attrs.getter_table {
+        return quote! {};
+    }
+    let Context { enum_path, value_variants, value_places, deref, .. } = cx;
+    // whether each variant is stored by its key, so that it might be missing
+    let mut map_backed_arms: Vec<_> = cx.fields.iter().map(|Field { normal, storage, .. }| match storage {
+        Some(storage) => {
+            let map_backed = !matches!(storage, Storage::Value);
+            quote! { #enum_path::#normal { .. } => #map_backed }
+        }
+        None => quote! { #enum_path::#normal { .. } => unreachable!() }
+    }).collect();
+    if cx.attrs.catch_all.is_some() {
+    map_backed_arms.push(quote! { #[allow(unreachable_patterns)] _ => true });
+    }
+    let scrutinee = cx.scrutinee();
+    let value_count = value_places.len();
+    let unit_indices = 0..value_count;
+    quote! {
+        /// Returns whether the variant is stored by its key (in a hashmap or dense storage), so that `get()` might
+        /// return `None` for it. Unit variants always have a value, so `get_unchecked()` can't panic for them.
+        #[must_use]
+        pub const fn is_map_backed(var: &#enum_path) -> bool {
+            match #scrutinee {
+                #(#map_backed_arms),*
+            }
+        }
+
+        /// Returns the position of a unit variant's getter in `GETTERS` and `GETTERS_MUT`, or `None` for a variant that
+        /// is stored by its key.
+        #[must_use]
+        pub const fn unit_index(var: &#enum_path) -> Option<usize> {
+            #[allow(unreachable_patterns)]
+            match var {
+                #(#value_variants => Some(#unit_indices),)*
+                _ => None
+            }
+        }
+
+        /// Functions returning the value of each unit variant, in the same order as `iter_unit()`.
+        pub const GETTERS: [fn(&Self) -> &T; #value_count] = [#(|this| &#deref this.#value_places),*];
+
+        /// Functions returning a mutable reference to the value of each unit variant, like `GETTERS`.
+        pub const GETTERS_MUT: [fn(&mut Self) -> &mut T; #value_count] = [#(|this| &mut #deref this.#value_places),*];
+    }
+}
+
+/// With `handlers`, a variants struct of boxed functions can be called through the variants.
+pub(super) fn handlers(cx: &Context) -> Option<proc_macro2::TokenStream> {
+    let (span, name) = cx.attrs.handlers.as_ref()?;
+    let Context { enum_path, visibility, helper_hidden, impl_attrs, .. } = cx;
+    let EnumAttrs { struct_ident, bounds, .. } = cx.attrs;
+    let handlers_ident = name.clone().unwrap_or_else(|| format_ident!("{}Handlers", struct_ident, span = *span));
+    Some(quote! {
+        #helper_hidden
+        #visibility type #handlers_ident<Args, Out> = #struct_ident<Box<dyn Fn(Args) -> Out + Send + Sync>>;
+
+        #impl_attrs
+        impl<Args, Out> #struct_ident<Box<dyn Fn(Args) -> Out + Send + Sync>>
+            where Box<dyn Fn(Args) -> Out + Send + Sync>: #(#bounds)+* {
+            #[track_caller]
+            pub fn call(&self, var: &#enum_path, args: Args) -> Out {
+                self.get_unchecked(var)(args)
+            }
+
+            pub fn try_call(&self, var: &#enum_path, args: Args) -> Option<Out> {
+                self.get(var).map(|handler| handler(args))
+            }
+        }
+    })
+}
+
+/// With `visitor`, the visitor trait has a method for each field that does nothing by default, so implementations only
+/// have to handle the fields they care about.
+pub(super) fn visitor(cx: &Context) -> Option<proc_macro2::TokenStream> {
+    if !cx.attrs.visitor {
+        return None;
+    }
+    let Context { enum_path, visibility, deref, helper_hidden, case_lint, impl_attrs, .. } = cx;
+    let EnumAttrs { struct_ident, bounds, catch_all, .. } = cx.attrs;
+    // each field has a method in the visitor trait, which `accept` calls for each of its values
+    let mut visit_methods = vec![];
+    let mut visit_calls = vec![];
+    for (Field { normal, snake, place, .. }, storage) in cx.stored() {
+        let variant_name = format!("`{}::{}`", cx.enum_name, normal);
+        let unit = quote! { #deref self.#place };
+        let visit_name = format_ident!("visit_{}", snake.unraw(), span = snake.span());
+        let (visit_doc, visit_method, visit_call) = match storage {
+            Storage::Value => (
+                format!("Visits the value of {}.", variant_name),
+                quote! { fn #visit_name(&mut self, value: &T) { let _ = value; } },
+                quote! { visitor.#visit_name(&#unit); }
+            ),
+            Storage::Map(ty) => (
+                format!("Visits each value of {}, along with its key.", variant_name),
+                quote! { fn #visit_name(&mut self, key: &#ty, value: &T) { let _ = (key, value); } },
+                quote! {
+                    for (key, value) in &self.#snake {
+                        visitor.#visit_name(key, value);
+                    }
+                }
+            ),
+            Storage::Dense(ty, keys, _) => {
+                let key_at = keys.key(ty);
+                (
+                    format!("Visits each value of {}, along with its key.", variant_name),
+                    quote! { fn #visit_name(&mut self, key: &#ty, value: &T) { let _ = (key, value); } },
+                    quote! {
+                        for (index, value) in self.#snake.iter().enumerate() {
+                            if let Some(value) = value {
+                                visitor.#visit_name(&(#key_at), value);
+                            }
+                        }
+                    }
+                )
+            }
+            Storage::Multi(ty) => (
+                format!("Visits each value of {}, along with its key (which is repeated for each of its values).", variant_name),
+                quote! { fn #visit_name(&mut self, key: &#ty, value: &T) { let _ = (key, value); } },
+                quote! {
+                    for (key, values) in &self.#snake {
+                        for value in values {
+                            visitor.#visit_name(key, value);
+                        }
+                    }
+                }
+            )
+        };
+        visit_methods.push(quote! {
+            #[doc = #visit_doc]
+            #visit_method
+        });
+        visit_calls.push(visit_call);
+    }
+    for Merged { path: merged, merged_struct, snake } in &cx.merged {
+        let merged_name = quote!(#merged).to_string().replace(' ', "");
+        let visit_name = format_ident!("visit_{}", snake.unraw(), span = snake.span());
+        let visit_doc = format!("Visits the merged struct of `{}`, which can accept its own visitor if it has one.", merged_name);
+        visit_methods.push(quote! {
+            #[doc = #visit_doc]
+            fn #visit_name(&mut self, value: &#merged_struct<T>) { let _ = value; }
+        });
+        visit_calls.push(quote! { visitor.#visit_name(&self.#snake); });
+    }
+    if let Some(catch_all) = catch_all {
+        let visit_name = format_ident!("visit_{}", catch_all.unraw(), span = catch_all.span());
+        visit_methods.push(quote! {
+            /// Visits each value of the variants that are stored in the catch-all field, along with its variant.
+            fn #visit_name(&mut self, var: &#enum_path, value: &T) { let _ = (var, value); }
+        });
+        visit_calls.push(quote! {
+            for (var, value) in &self.#catch_all {
+                visitor.#visit_name(var, value);
+            }
+        });
+    }
+
+    let visitor_ident = format_ident!("{}Visitor", struct_ident);
+    let visitor_doc = format!(
+        "Visits the values of a `{}`, with a method for each field. Pass it to `{}::accept()`.",
+        struct_ident, struct_ident
+    );
+    let accept_body = if visit_calls.is_empty() { quote! { let _ = visitor; } } else { quote! { #(#visit_calls)* } };
+    Some(quote! {
+        #[doc = #visitor_doc]
+        #helper_hidden
+        #case_lint
+        #visibility trait #visitor_ident<T: #(#bounds)+*> {
+            #(#visit_methods)*
+        }
+
+        #case_lint
+        #impl_attrs
+        impl<T: #(#bounds)+*> #struct_ident<T> {
+            /// Calls the method of the visitor for each field, in the order of the fields.
+            pub fn accept<V: #visitor_ident<T> + ?Sized>(&self, visitor: &mut V) {
+                #accept_body
+            }
+        }
+    })
+}
+
+/// With `markers`, each variant gets a marker type that selects its field at compile time.
+pub(super) fn markers(cx: &Context) -> Option<proc_macro2::TokenStream> {
+    let module = cx.attrs.markers.as_ref()?;
+    let Context { visibility, deref, map_ty, helper_hidden, case_lint, derived, impl_attrs, .. } = cx;
+    let EnumAttrs { struct_ident, bounds, .. } = cx.attrs;
+    let enum_ident = &cx.input.ident;
+    // the marker types of the variants, and the types of their fields
+    let mut marker_idents = vec![];
+    let mut marker_fields = vec![];
+    let mut marker_tys = vec![];
+    for (Field { normal, place, .. }, storage) in cx.stored() {
+        marker_idents.push(normal);
+        marker_fields.push(match storage {
+            Storage::Value => quote! { #deref table.#place },
+            _ => quote! { table.#place }
+        });
+        marker_tys.push(match storage {
+            Storage::Value => quote! { T },
+            Storage::Map(ty) => quote! { #map_ty<#ty, T> },
+            Storage::Dense(_, keys, array) => keys.slots_ty(*array),
+            Storage::Multi(ty) => quote! { #map_ty<#ty, Vec<T>> }
+        });
+    }
+    let marker_trait = format_ident!("{}Marker", enum_ident);
+    let bound_list = quote! { #(#bounds)+* };
+    Some(quote! {
+        #helper_hidden
+        #visibility mod #module {
+            #(
+                #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+                pub struct #marker_idents;
+            )*
+        }
+
+        #helper_hidden
+        #visibility trait #marker_trait<T: #bound_list> {
+            type Field;
+
+            fn field(table: &#struct_ident<T>) -> &Self::Field;
+
+            fn field_mut(table: &mut #struct_ident<T>) -> &mut Self::Field;
+        }
+
+        #(
+            #derived
+            #impl_attrs
+            impl<T: #bound_list> #marker_trait<T> for #module::#marker_idents {
+                type Field = #marker_tys;
+
+                fn field(table: &#struct_ident<T>) -> &Self::Field {
+                    &#marker_fields
+                }
+
+                fn field_mut(table: &mut #struct_ident<T>) -> &mut Self::Field {
+                    &mut #marker_fields
+                }
+            }
+        )*
+
+        #case_lint
+        #impl_attrs
+        impl<T: #bound_list> #struct_ident<T> {
+            pub fn get_typed<M: #marker_trait<T>>(&self) -> &M::Field {
+                M::field(self)
+            }
+
+            pub fn get_typed_mut<M: #marker_trait<T>>(&mut self) -> &mut M::Field {
+                M::field_mut(self)
+            }
+
+            pub fn set_typed<M: #marker_trait<T>>(&mut self, value: M::Field) -> M::Field {
+                std::mem::replace(M::field_mut(self), value)
+            }
+        }
+    })
+}
+
+/// With `cell`, the struct can be shared behind an `Arc` that is swapped out when it's updated.
+pub(super) fn cell(cx: &Context) -> Option<proc_macro2::TokenStream> {
+    let cell = cx.attrs.cell.as_ref()?;
+    let Context { visibility, helper_hidden, derived, impl_attrs, .. } = cx;
+    let EnumAttrs { struct_ident, bounds, .. } = cx.attrs;
+    let cell_doc = format!(
+        "Shares a `{}` between threads, for reading it much more often than it's updated.",
+        struct_ident
+    );
+    Some(quote! {
+        #[doc = #cell_doc]
+        #helper_hidden
+        #visibility struct #cell<T: #(#bounds)+*>(std::sync::RwLock<std::sync::Arc<#struct_ident<T>>>);
+
+        #impl_attrs
+        impl<T: #(#bounds)+*> #cell<T> {
+            /// Creates a cell holding the struct.
+            pub fn new(value: #struct_ident<T>) -> Self {
+                #cell(std::sync::RwLock::new(std::sync::Arc::new(value)))
+            }
+
+            /// Returns the struct that is currently stored. Updates don't change it, they store a new one.
+            #[must_use]
+            pub fn load(&self) -> std::sync::Arc<#struct_ident<T>> {
+                let current = self.0.read().unwrap_or_else(std::sync::PoisonError::into_inner);
+                std::sync::Arc::clone(&current)
+            }
+
+            /// Replaces the stored struct.
+            pub fn store(&self, value: #struct_ident<T>) {
+                drop(self.swap(value));
+            }
+
+            /// Replaces the stored struct, returning the old one.
+            pub fn swap(&self, value: #struct_ident<T>) -> std::sync::Arc<#struct_ident<T>> {
+                let value = std::sync::Arc::new(value);
+                let mut current = self.0.write().unwrap_or_else(std::sync::PoisonError::into_inner);
+                std::mem::replace(&mut current, value)
+            }
+
+            /// Stores a new struct made from the current one by `f`, returning the old one.
+            ///
+            /// `f` is called without holding the lock, so if the struct is replaced in the meantime, it's called
+            /// again with the new one.
+            pub fn rcu<F: FnMut(&#struct_ident<T>) -> #struct_ident<T>>(&self, mut f: F) -> std::sync::Arc<#struct_ident<T>> {
+                loop {
+                    let old = self.load();
+                    let new = std::sync::Arc::new(f(&old));
+                    let mut current = self.0.write().unwrap_or_else(std::sync::PoisonError::into_inner);
+                    if std::sync::Arc::ptr_eq(&current, &old) {
+                        *current = new;
+                        break old;
+                    }
+                }
+            }
+
+            /// Returns the struct that is currently stored, consuming the cell.
+            #[must_use]
+            pub fn into_inner(self) -> std::sync::Arc<#struct_ident<T>> {
+                self.0.into_inner().unwrap_or_else(std::sync::PoisonError::into_inner)
+            }
+        }
+
+        #derived
+        #impl_attrs
+        impl<T: #(#bounds)+*> From<#struct_ident<T>> for #cell<T> {
+            fn from(value: #struct_ident<T>) -> Self {
+                #cell::new(value)
+            }
+        }
+
+        #derived
+        #impl_attrs
+        impl<T: #(#bounds)+*> Default for #cell<T> where #struct_ident<T>: Default {
+            fn default() -> Self {
+                #cell::new(Default::default())
+            }
+        }
+    })
+}
+
+/// With `cross_eq`, structs with different value types are compared field by field, and key by key in the hashmaps.
+pub(super) fn cross_eq(cx: &Context) -> Option<proc_macro2::TokenStream> {
+    let path = cx.attrs.cross_eq.as_ref()?;
+    let Context { value_places, map_idents, dense_idents, multi_idents, deref, case_lint, derived, impl_attrs, .. } = cx;
+    let EnumAttrs { struct_ident, bounds, derives, .. } = cx.attrs;
+    // it implements `PartialEq` for every pair of value types, which would overlap with a derived one
+    if derives.iter().any(|derive| derive.segments.last().is_some_and(|segment| segment.ident == "PartialEq")) {
+        emit_error!(path, "`cross_eq` already implements `PartialEq`, so it can't also be in `struct_derive`");
+    }
+    let merged_idents = cx.merged_idents();
+    let merged_eq_bounds = cx.merged.iter().map(|Merged {merged_struct, ..}| quote! { #merged_struct<T>: PartialEq<#merged_struct<U>> });
+    let mut eq_checks: Vec<_> = value_places.iter().map(|place| quote! { #deref self.#place == #deref other.#place }).collect();
+    eq_checks.extend(map_idents.iter().chain(multi_idents.iter()).map(|ident| quote! {
+        self.#ident.len() == other.#ident.len()
+            && self.#ident.iter().all(|(key, value)| other.#ident.get(key).is_some_and(|other| value == other))
+    }));
+    eq_checks.extend(dense_idents.iter().map(|ident| quote! {
+        self.#ident.len() == other.#ident.len()
+            && self.#ident.iter().zip(&other.#ident).all(|slots| match slots {
+                (Some(value), Some(other)) => value == other,
+                (None, None) => true,
+                _ => false
+            })
+    }));
+    eq_checks.extend(merged_idents.iter().map(|ident| quote! { self.#ident == other.#ident }));
+    let eq_body = if eq_checks.is_empty() { quote! { let _ = other; true } } else { quote! { #(#eq_checks)&&* } };
+    Some(quote! {
+        #derived
+        #case_lint
+        #impl_attrs
+        impl<T: #(#bounds)+*, U: #(#bounds)+*> PartialEq<#struct_ident<U>> for #struct_ident<T>
+            where T: PartialEq<U>, #(#merged_eq_bounds),* {
+            fn eq(&self, other: &#struct_ident<U>) -> bool {
+                #eq_body
+            }
+        }
+    })
+}
+
+/// With `async_locks`, each value is kept behind its own `tokio::sync` lock, so tasks only wait for the values they use.
+pub(super) fn async_locks(cx: &Context) -> Option<proc_macro2::TokenStream> {
+    let (path, mutex, tokio) = cx.attrs.async_locks.as_ref()?;
+    let Context { enum_path, visibility, vars, value_idents, value_variants, deref, helper_hidden, case_lint, derived, impl_attrs, .. } = cx;
+    let EnumAttrs { struct_ident, bounds, catch_all, merge_with, .. } = cx.attrs;
+    let jump_table = cx.jump_table;
+    if vars.is_empty() {
+        emit_error!(path, "`async_locks` needs at least one variant to lock");
+    }
+    for var in vars.iter() {
+        if !matches!(var.storage, Some(Storage::Value)) {
+            emit_error!(var.normal, "`async_locks` can only be used on enums with only unit variants");
+        }
+    }
+    if let Some(catch_all) = &catch_all {
+        emit_error!(catch_all, "`async_locks` can't be used with `catch_all`");
+    }
+    if let Some((merged, _)) = merge_with.first() {
+        emit_error!(merged, "`async_locks` can't be used with `merge_with`");
+    }
+    let locks = format_ident!("{}Locks", struct_ident, span = path.span());
+    let locks_doc = format!("Shares a `{}` between async tasks, with a separate lock for each value.", struct_ident);
+    let destructure = if jump_table {
+        quote! { let [#(#value_idents),*] = value.values; }
+    } else {
+        quote! { let #struct_ident { #(#value_idents,)* .. } = value; }
+    };
+    let (lock_ty, accessors) = if *mutex {
+        (quote! { #tokio::sync::Mutex }, quote! {
+            /// Waits until the variant's value can be locked.
+            pub async fn lock(&self, var: &#enum_path) -> #tokio::sync::MutexGuard<'_, T> {
+                match var {
+                    #(#value_variants => self.#value_idents.lock().await,)*
+                }
+            }
+        })
+    } else {
+        (quote! { #tokio::sync::RwLock }, quote! {
+            /// Waits until the variant's value can be read.
+            pub async fn read(&self, var: &#enum_path) -> #tokio::sync::RwLockReadGuard<'_, T> {
+                match var {
+                    #(#value_variants => self.#value_idents.read().await,)*
+                }
+            }
+
+            /// Waits until the variant's value can be written.
+            pub async fn write(&self, var: &#enum_path) -> #tokio::sync::RwLockWriteGuard<'_, T> {
+                match var {
+                    #(#value_variants => self.#value_idents.write().await,)*
+                }
+            }
+        })
+    };
+    Some(quote! {
+        #[doc = #locks_doc]
+        #helper_hidden
+        #visibility struct #locks<T: #(#bounds)+*> {
+            #(pub #value_idents: #lock_ty<T>),*
+        }
+
+        #case_lint
+        #impl_attrs
+        impl<T: #(#bounds)+*> #locks<T> {
+            /// Puts each of the struct's values behind a lock.
+            pub fn new(value: #struct_ident<T>) -> Self {
+                #destructure
+                #locks {
+                    #(#value_idents: #lock_ty::new(#deref #value_idents)),*
+                }
+            }
+
+            #accessors
+
+            /// Takes the values out of the locks, consuming them.
+            #[must_use]
+            pub fn into_inner(self) -> #struct_ident<T> {
+                #struct_ident::new(#(self.#value_idents.into_inner()),*)
+            }
+        }
+
+        #derived
+        #impl_attrs
+        impl<T: #(#bounds)+*> From<#struct_ident<T>> for #locks<T> {
+            fn from(value: #struct_ident<T>) -> Self {
+                #locks::new(value)
+            }
+        }
+
+        #derived
+        #impl_attrs
+        impl<T: #(#bounds)+*> Default for #locks<T> where #struct_ident<T>: Default {
+            fn default() -> Self {
+                #locks::new(Default::default())
+            }
+        }
+    })
+}
+
+/// With `impl_for`, numeric conveniences are generated for concrete value types, without bounding `T` in general.
+pub(super) fn impl_for(cx: &Context) -> proc_macro2::TokenStream {
+    let Context { case_lint, impl_attrs, clone_bounds, .. } = cx;
+    let struct_ident = &cx.attrs.struct_ident;
+    let impl_for_items = cx.attrs.impl_fors.iter().map(|ImplFor {ty, extras}| {
+        let methods = extras.iter().map(|extra| match extra.to_string().as_str() {
+            "sum" => quote! {
+                /// Adds up all of the values.
+                #[must_use]
+                pub fn #extra(&self) -> #ty where #(#clone_bounds),* {
+                    self.iter().map(|(_, value)| value).sum()
+                }
+            },
+            "product" => quote! {
+                /// Multiplies all of the values.
+                #[must_use]
+                pub fn #extra(&self) -> #ty where #(#clone_bounds),* {
+                    self.iter().map(|(_, value)| value).product()
+                }
+            },
+            "min" | "max" => {
+                let (doc, beats) = if extra == "min" {
+                    ("Returns the smallest value, or `None` if there are no values.", quote! { < })
+                } else {
+                    ("Returns the largest value, or `None` if there are no values.", quote! { > })
+                };
+                quote! {
+                    #[doc = #doc]
+                    #[must_use]
+                    pub fn #extra(&self) -> Option<&#ty> where #(#clone_bounds),* {
+                        self.iter().map(|(_, value)| value).fold(None, |best, value| match best {
+                            Some(best) if !(value #beats best) => Some(best),
+                            _ => Some(value)
+                        })
+                    }
+                }
+            }
+            "lerp" => quote! {
+                /// Interpolates each value towards the same variant's value in `other`, by `t` (where `0` keeps this value
+                /// and `1` gives the other). Values that `other` doesn't have are kept as they are.
+                #[must_use]
+                pub fn #extra(&self, other: &Self, t: #ty) -> Self where #(#clone_bounds),* {
+                    let mut result = self.map_ref(Clone::clone);
+                    for (var, value) in result.iter_mut() {
+                        if let Some(end) = other.get(&var) {
+                            let start = value.clone();
+                            *value = start.clone() + (end.clone() - start) * t.clone();
+                        }
+                    }
+                    result
+                }
+            },
+            _ => quote! {}
+        });
+        quote! {
+            #case_lint
+            #impl_attrs
+            impl #struct_ident<#ty> {
+                #(#methods)*
+            }
+        }
+    });
+    quote! { #(#impl_for_items)* }
+}
+
+/// With `random`, structs can be sampled from `rand`'s `Standard` distribution, which samples every unit variant.
+pub(super) fn random(cx: &Context) -> Option<proc_macro2::TokenStream> {
+    let rand = cx.attrs.random.as_ref()?;
+    let Context { value_idents, prefill_bounds, case_lint, derived, impl_attrs, .. } = cx;
+    let EnumAttrs { struct_ident, bounds, .. } = cx.attrs;
+    let merged_idents = cx.merged_idents();
+    let merged_tys = cx.merged_tys();
+    Some(quote! {
+        #derived
+        #case_lint
+        #impl_attrs
+        impl<T: #(#bounds)+*> #rand::distributions::Distribution<#struct_ident<T>> for #rand::distributions::Standard
+            where #rand::distributions::Standard: #rand::distributions::Distribution<T>
+                #(+ #rand::distributions::Distribution<#merged_tys>)* #(, #prefill_bounds)* {
+            fn sample<R: #rand::Rng + ?Sized>(&self, rng: &mut R) -> #struct_ident<T> {
+                #(let #value_idents: T = self.sample(rng);)*
+                #(let #merged_idents: #merged_tys = self.sample(rng);)*
+                #struct_ident::new(#(#value_idents,)* #(#merged_idents),*)
+            }
+        }
+    })
+}
+
+/// With `for_each_macro`, a `macro_rules!` macro calls another macro once for each (variant, field) pair, passing along
+/// any extra tokens.
+pub(super) fn for_each_macro(cx: &Context) -> Option<proc_macro2::TokenStream> {
+    let for_each_ident = cx.attrs.for_each_macro.as_ref()?;
+    let for_each_pairs = cx.stored().map(|(Field { normal, snake, .. }, _)| quote! { #normal, #snake });
+    Some(quote! {
+        #[allow(unused_macros)]
+        macro_rules! #for_each_ident {
+            ($callback:ident $($args:tt)*) => {
+                #($callback!(#for_each_pairs $($args)*);)*
+            };
+        }
+    })
+}
+
+/// With `serde_pairs`, the struct is serialized as a sequence of variants and values.
+pub(super) fn serde_pairs(cx: &Context) -> Option<proc_macro2::TokenStream> {
+    let path = cx.attrs.serde_pairs.as_ref()?;
+    let Context { enum_path, value_idents, value_variants, derived, case_lint, impl_attrs, clone_bounds, .. } = cx;
+    let EnumAttrs { struct_ident, bounds, derives, merge_with, .. } = cx.attrs;
+    let is_serde = |derive: &syn::Path| derive.segments.last()
+        .is_some_and(|segment| segment.ident == "Serialize" || segment.ident == "Deserialize");
+    if derives.iter().any(is_serde) {
+        emit_error!(path, "`serde_pairs` implements `Serialize` and `Deserialize`, so they can't be derived too");
+    }
+    if let Some((merged, _)) = merge_with.first() {
+        emit_error!(merged, "`serde_pairs` can't be used with `merge_with`");
+    }
+    let field_names: Vec<_> = value_idents.iter().map(|ident| ident.unraw().to_string()).collect();
+    // whether the key of a dense variant fits in its field, for deserializing
+    let dense_checks = cx.stored().filter_map(|(Field { snake, variant, .. }, storage)| match storage {
+        Storage::Dense(ty, keys, _) => {
+            let key_index = keys.index(ty, quote! { *key });
+            Some(quote! { #variant => (#key_index) < result.#snake.len() })
+        }
+        _ => None
+    });
+    let unfilled_body = storage::construct(cx, false);
+    Some(quote! {
+        #derived
+        #impl_attrs
+        impl<T: serde::Serialize #(+ #bounds)*> serde::Serialize for #struct_ident<T>
+            where #enum_path: serde::Serialize, #(#clone_bounds),* {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_seq(self.iter())
+            }
+        }
+
+        #derived
+        #case_lint
+        #impl_attrs
+        impl<'de, T: serde::Deserialize<'de> #(+ #bounds)*> serde::Deserialize<'de> for #struct_ident<T>
+            where #enum_path: serde::Deserialize<'de> {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let pairs: Vec<(#enum_path, T)> = serde::Deserialize::deserialize(deserializer)?;
+                #(let mut #value_idents = None;)*
+                let mut rest = Vec::new();
+                for (var, value) in pairs {
+                    #[allow(unreachable_patterns)]
+                    match var {
+                        #(#value_variants => #value_idents = Some(value),)*
+                        var => rest.push((var, value))
+                    }
+                }
+                #(let #value_idents = #value_idents.ok_or_else(|| serde::de::Error::missing_field(#field_names))?;)*
+                // not `new()`, which would fill in keys that weren't serialized
+                let mut result = #unfilled_body;
+                for (var, value) in rest {
+                    #[allow(unreachable_patterns)]
+                    let in_bounds = match &var {
+                        #(#dense_checks,)*
+                        _ => true
+                    };
+                    if !in_bounds {
+                        return Err(serde::de::Error::custom("dense key is not below the maximum, or not one of the keys of the variant"));
+                    }
+                    result.push(var, value);
+                }
+                Ok(result)
+            }
+        }
+    })
+}
+
+/// Each table nests the variants struct of its columns in this one.
+pub(super) fn tables(cx: &Context) -> proc_macro2::TokenStream {
+    let Context { enum_path, visibility, helper_hidden, derived, impl_attrs, .. } = cx;
+    let EnumAttrs { struct_ident, bounds, tables, .. } = cx.attrs;
+    let table_items: Vec<_> = tables.iter().map(|Table { cols, cols_struct, name }| quote! {
+        #helper_hidden
+        #visibility struct #name<T>(pub #struct_ident<#cols_struct<T>>) where #cols_struct<T>: #(#bounds)+*;
+
+        #impl_attrs
+        impl<T> #name<T> where #cols_struct<T>: #(#bounds)+* {
+            pub fn new(rows: #struct_ident<#cols_struct<T>>) -> #name<T> {
+                #name(rows)
+            }
+
+            pub fn get(&self, row: &#enum_path, col: &#cols) -> Option<&T> {
+                self.0.get(row).and_then(|cols| cols.get(col))
+            }
+
+            pub fn get_mut(&mut self, row: &#enum_path, col: &#cols) -> Option<&mut T> {
+                self.0.get_mut(row).and_then(|cols| cols.get_mut(col))
+            }
+
+            #[track_caller]
+            pub fn get_unchecked(&self, row: &#enum_path, col: &#cols) -> &T {
+                self.get(row, col).expect("entry not found in table")
+            }
+
+            #[track_caller]
+            pub fn get_mut_unchecked(&mut self, row: &#enum_path, col: &#cols) -> &mut T {
+                self.get_mut(row, col).expect("entry not found in table")
+            }
+        }
+
+        #derived
+        #impl_attrs
+        impl<T> std::ops::Index<(#enum_path, #cols)> for #name<T> where #cols_struct<T>: #(#bounds)+* {
+            type Output = T;
+
+            #[track_caller]
+            fn index(&self, (row, col): (#enum_path, #cols)) -> &T {
+                self.get_unchecked(&row, &col)
+            }
+        }
+
+        #derived
+        #impl_attrs
+        impl<T> std::ops::IndexMut<(#enum_path, #cols)> for #name<T> where #cols_struct<T>: #(#bounds)+* {
+            #[track_caller]
+            fn index_mut(&mut self, (row, col): (#enum_path, #cols)) -> &mut T {
+                self.get_mut_unchecked(&row, &col)
+            }
+        }
+    }).collect();
+    quote! { #(#table_items)* }
+}
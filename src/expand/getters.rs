@@ -0,0 +1,698 @@
+//! The getters of the struct, which look up the value of a variant, and the accessors of its fields.
+
+use syn::{Ident, ItemEnum, Fields, spanned::Spanned, ext::IdentExt};
+use quote::{quote, quote_spanned, format_ident};
+
+use super::{Context, EnumAttrs, Field, Merged, Part, Storage, VariantInfo};
+
+/// The methods that are always generated on the struct, which the per-field accessors must not collide with.
+const METHOD_NAMES: &[&str] = &[
+    "new", "new_default", "with_capacity", "reserve", "shrink_to_fit", "clear", "reset", "drain", "len", "is_empty", "to_vec", "into_values",
+    "iter", "iter_mut", "iter_unit", "iter_unit_mut", "iter_map", "iter_map_mut",
+    "get_unchecked", "get_mut_unchecked", "get", "get_mut", "push", "get_all",
+    "get_with_fallback", "get_or_else", "gather", "apply_defaults", "apply_defaults_where", "validate_all", "heap_usage", "heap_usage_with", "get_key_value", "get_by_name", "get_by_name_mut", "invert",
+    "transpose", "map_ref", "each_ref", "snapshot", "restore", "get_typed", "get_typed_mut", "set_typed"
+];
+
+/// matching on it. If the discriminants aren't the field positions already, a const table maps them to the positions.
+///
+/// Returns `None` if the enum has variants with `()` fields (or no variants), which can't be cast.
+fn jump_table_index_fn(
+    input: &ItemEnum,
+    enum_path: &syn::Path,
+    vars: &[VariantInfo],
+    repr: &Option<Ident>,
+    is_local: bool,
+    span: proc_macro2::Span
+) -> Option<proc_macro2::TokenStream> {
+    if input.variants.is_empty() || !input.variants.iter().all(|var| matches!(var.fields, Fields::Unit)) {
+        return None;
+    }
+    // a copy of a remote enum might leave its discriminants out, so only local ones are trusted to count up from 0
+    let in_declaration_order = is_local
+        && input.variants.iter().all(|var| var.discriminant.is_none())
+        && vars.iter().map(|var| var.normal).eq(input.variants.iter().map(|var| &var.ident));
+    // spanned so that a missing `Copy` is reported at the attribute (`var` keeps the call site, which it's declared with)
+    let copied = quote_spanned! {span=> copied};
+    let copied = quote! { #copied(var) };
+    let index = if in_declaration_order {
+        quote! { #copied as usize }
+    } else {
+        let repr = repr.clone().unwrap_or_else(|| Ident::new("isize", span));
+        let normals = vars.iter().map(|var| var.normal);
+        let count = vars.len();
+        quote! {
+            // the discriminants in the order of the fields, and the position of each one's field by its offset from the smallest
+            const DISCRIMINANTS: [#repr; #count] = [#(#enum_path::#normals as #repr),*];
+            const MIN: #repr = {
+                let mut min = DISCRIMINANTS[0];
+                let mut i = 1;
+                while i < #count {
+                    if DISCRIMINANTS[i] < min {
+                        min = DISCRIMINANTS[i];
+                    }
+                    i += 1;
+                }
+                min
+            };
+            const LEN: usize = {
+                let mut max = DISCRIMINANTS[0];
+                let mut i = 1;
+                while i < #count {
+                    if DISCRIMINANTS[i] > max {
+                        max = DISCRIMINANTS[i];
+                    }
+                    i += 1;
+                }
+                max.wrapping_sub(MIN) as usize + 1
+            };
+            const INDICES: [usize; LEN] = {
+                let mut indices = [0; LEN];
+                let mut i = 0;
+                while i < #count {
+                    indices[DISCRIMINANTS[i].wrapping_sub(MIN) as usize] = i;
+                    i += 1;
+                }
+                indices
+            };
+            INDICES[(#copied as #repr).wrapping_sub(MIN) as usize]
+        }
+    };
+    Some(quote! {
+        const fn variant_index(var: &#enum_path) -> usize {
+            #[diagnostic::on_unimplemented(
+                message = "`jump_table` needs `{Self}` to be `Copy`",
+                label = "the index of a variant is found by casting it to its discriminant"
+            )]
+            trait JumpTableEnum: Copy {}
+            impl<E: Copy> JumpTableEnum for E {}
+            const fn copied<E: JumpTableEnum>(var: &E) -> E {
+                *var
+            }
+            #index
+        }
+    })
+}
+
+/// Generates the methods that look up variants, and the `Index` impls that go through them.
+pub(super) fn generate(cx: &Context) -> Part {
+    let Context { enum_path, value_variants, deref, value_ty, into_value, derived, impl_attrs, clone_bounds, .. } = cx;
+    let EnumAttrs { struct_ident, bounds, catch_all, auto_insert, .. } = cx.attrs;
+    let (auto_insert, jump_table) = (*auto_insert, cx.jump_table);
+    let dense_idents = &cx.dense_idents;
+
+    // the match arms of each getter, which the unit variants of a jump table don't need
+    let mut gets = vec![];
+    let mut get_muts = vec![];
+    let mut get_uncheckeds = vec![];
+    let mut get_mut_uncheckeds = vec![];
+    let mut pushes = vec![];
+    let mut get_alls = vec![];
+    let mut key_values = vec![];
+    // the field names of the unit variants, and where their values are, for looking them up by name
+    let mut unit_names = vec![];
+    let mut unit_name_places = vec![];
+    // with `auto_insert`, the mutable unchecked getter has to clone keys to insert them
+    let mut auto_insert_bounds = vec![];
+    for Field { normal, snake, variant, storage, place, .. } in &cx.fields {
+        let storage = match storage {
+            Some(storage) => storage,
+            None => {
+                let arm = quote! { #enum_path::#normal { .. } => unreachable!() };
+                gets.push(arm.clone());
+                get_muts.push(arm.clone());
+                get_uncheckeds.push(arm.clone());
+                get_mut_uncheckeds.push(arm.clone());
+                pushes.push(arm.clone());
+                key_values.push(arm.clone());
+                get_alls.push(arm);
+                continue;
+            }
+        };
+        // the value of a unit variant, as a place of type `T`
+        let unit = quote! { #deref self.#place };
+        match storage {
+            Storage::Value => {
+                key_values.push(quote! { #variant => Some((#variant, &#unit)) });
+                unit_names.push(snake.unraw().to_string());
+                unit_name_places.push(unit.clone());
+                // the getters of a jump table don't need any match arms
+                if !jump_table {
+                    gets.push(quote! { #variant => Some(&#unit) });
+                    get_muts.push(quote! { #variant => Some(&mut #unit) });
+                    get_uncheckeds.push(quote! { #variant => &#unit });
+                    get_mut_uncheckeds.push(quote! { #variant => &mut #unit });
+                    pushes.push(quote! { #variant => #unit = value });
+                    get_alls.push(quote! { #variant => std::slice::from_ref(&#unit) });
+                }
+            }
+            Storage::Map(ty) => {
+                gets.push(quote! {
+                    #variant => self.#snake.get(key)
+                });
+                key_values.push(quote! {
+                    #variant => self.#snake.get_key_value(key).map(|(key, value)| {
+                        let key = key.clone();
+                        (#variant, value)
+                    })
+                });
+                get_muts.push(quote! {
+                    #variant => self.#snake.get_mut(key)
+                });
+                get_uncheckeds.push(quote! {
+                    #variant => self.#snake.get(key)
+                        .expect("tuple variant key not found in hashmap")
+                });
+                if auto_insert {
+                    get_mut_uncheckeds.push(quote! {
+                        #variant => self.#snake.entry(key.clone()).or_default()
+                    });
+                    auto_insert_bounds.push(quote! { for<'k> #ty: Clone });
+                } else {
+                    get_mut_uncheckeds.push(quote! {
+                        #variant => self.#snake.get_mut(key)
+                            .expect("tuple variant key not found in hashmap")
+                    });
+                }
+                pushes.push(quote! { #variant => { self.#snake.insert(key, value); } });
+                get_alls.push(quote! { #variant => self.#snake.get(key).map(std::slice::from_ref).unwrap_or(&[]) });
+            }
+            Storage::Dense(ty, keys, _) => {
+                let key_index = keys.index(ty, quote! { *key });
+                let owned_index = keys.index(ty, quote! { key });
+                let bound = keys.bound();
+                let missing_message = format!("tuple variant key is not {}", bound);
+                let push_message = format!("dense key is not {}", bound);
+                gets.push(quote! {
+                    #variant => self.#snake.get(#key_index).and_then(Option::as_ref)
+                });
+                key_values.push(quote! {
+                    #variant => self.#snake.get(#key_index).and_then(Option::as_ref).map(|value| {
+                        let key = *key;
+                        (#variant, value)
+                    })
+                });
+                get_muts.push(quote! {
+                    #variant => self.#snake.get_mut(#key_index).and_then(Option::as_mut)
+                });
+                get_uncheckeds.push(quote! {
+                    #variant => self.#snake.get(#key_index).and_then(Option::as_ref)
+                        .expect("tuple variant key not found in dense storage")
+                });
+                if auto_insert {
+                    get_mut_uncheckeds.push(quote! {
+                        #variant => self.#snake.get_mut(#key_index)
+                            .expect(#missing_message)
+                            .get_or_insert_with(T::default)
+                    });
+                } else {
+                    get_mut_uncheckeds.push(quote! {
+                        #variant => self.#snake.get_mut(#key_index).and_then(Option::as_mut)
+                            .expect("tuple variant key not found in dense storage")
+                    });
+                }
+                pushes.push(quote! {
+                    #variant => match self.#snake.get_mut(#owned_index) {
+                        Some(slot) => *slot = Some(value),
+                        None => panic!(#push_message)
+                    }
+                });
+                get_alls.push(quote! {
+                    #variant => self.#snake.get(#key_index).and_then(Option::as_ref).map(std::slice::from_ref).unwrap_or(&[])
+                });
+            }
+            Storage::Multi(ty) => {
+                gets.push(quote! {
+                    #variant => self.#snake.get(key).and_then(|values| values.first())
+                });
+                key_values.push(quote! {
+                    #variant => self.#snake.get_key_value(key).and_then(|(key, values)| values.first().map(|value| {
+                        let key = key.clone();
+                        (#variant, value)
+                    }))
+                });
+                get_muts.push(quote! {
+                    #variant => self.#snake.get_mut(key).and_then(|values| values.first_mut())
+                });
+                get_uncheckeds.push(quote! {
+                    #variant => self.#snake.get(key).and_then(|values| values.first())
+                        .expect("tuple variant key not found in hashmap")
+                });
+                if auto_insert {
+                    get_mut_uncheckeds.push(quote! {
+                        #variant => {
+                            let values = self.#snake.entry(key.clone()).or_default();
+                            if values.is_empty() {
+                                values.push(T::default());
+                            }
+                            &mut values[0]
+                        }
+                    });
+                    auto_insert_bounds.push(quote! { for<'k> #ty: Clone });
+                } else {
+                    get_mut_uncheckeds.push(quote! {
+                        #variant => self.#snake.get_mut(key).and_then(|values| values.first_mut())
+                            .expect("tuple variant key not found in hashmap")
+                    });
+                }
+                pushes.push(quote! { #variant => self.#snake.entry(key).or_default().push(value) });
+                get_alls.push(quote! { #variant => self.#snake.get(key).map(Vec::as_slice).unwrap_or(&[]) });
+            }
+        }
+    }
+    if let Some(catch_all) = catch_all {
+        gets.push(quote! { #[allow(unreachable_patterns)] other => self.#catch_all.get(other) });
+        key_values.push(quote! {
+            #[allow(unreachable_patterns)]
+            other => self.#catch_all.get_key_value(other).map(|(var, value)| (var.clone(), value))
+        });
+        get_muts.push(quote! { #[allow(unreachable_patterns)] other => self.#catch_all.get_mut(other) });
+        get_uncheckeds.push(quote! {
+            #[allow(unreachable_patterns)]
+            other => self.#catch_all.get(other).expect("variant not found in catch-all hashmap")
+        });
+        if auto_insert {
+            get_mut_uncheckeds.push(quote! {
+                #[allow(unreachable_patterns)]
+                other => self.#catch_all.entry(other.clone()).or_default()
+            });
+            auto_insert_bounds.push(quote! { for<'k> #enum_path: Clone });
+        } else {
+            get_mut_uncheckeds.push(quote! {
+                #[allow(unreachable_patterns)]
+                other => self.#catch_all.get_mut(other).expect("variant not found in catch-all hashmap")
+            });
+        }
+        pushes.push(quote! { #[allow(unreachable_patterns)] other => { self.#catch_all.insert(other, value); } });
+        get_alls.push(quote! {
+            #[allow(unreachable_patterns)]
+            other => self.#catch_all.get(other).map(std::slice::from_ref).unwrap_or(&[])
+        });
+    }
+
+    // an empty enum can only be matched by value
+    let scrutinee = cx.scrutinee();
+
+    // a jump table looks up the index of the variant once, instead of matching on it in every getter
+    let jump_table_span = cx.attrs.jump_table.as_ref().map_or_else(proc_macro2::Span::call_site, Spanned::span);
+    let (get_body, get_mut_body, get_unchecked_body, get_mut_unchecked_body, push_body, get_all_body, index_fn) = if jump_table {
+        let indices = 0..value_variants.len();
+        (
+            quote! { Some(&self.values[Self::variant_index(var)]) },
+            quote! { Some(&mut self.values[Self::variant_index(var)]) },
+            quote! { &self.values[Self::variant_index(var)] },
+            quote! { &mut self.values[Self::variant_index(var)] },
+            quote! { self.values[Self::variant_index(&var)] = value; },
+            quote! { std::slice::from_ref(&self.values[Self::variant_index(var)]) },
+            jump_table_index_fn(cx.input, enum_path, cx.vars, &cx.attrs.repr, cx.is_local, jump_table_span).unwrap_or_else(|| quote! {
+                fn variant_index(var: &#enum_path) -> usize {
+                    match #scrutinee {
+                        #(#value_variants => #indices),*
+                    }
+                }
+            })
+        )
+    } else {
+        (
+            quote! { match #scrutinee { #(#gets),* } },
+            quote! { match #scrutinee { #(#get_muts),* } },
+            quote! { match #scrutinee { #(#get_uncheckeds),* } },
+            quote! { match #scrutinee { #(#get_mut_uncheckeds),* } },
+            quote! { match var { #(#pushes),* } },
+            quote! { match #scrutinee { #(#get_alls),* } },
+            quote! {}
+        )
+    };
+
+    // the generated docs only explain missing values if there are variants with keys
+    let has_keys = cx.has_keys();
+    let enum_name = &cx.enum_name;
+    let missing_doc = if has_keys {
+        "Returns `None` if the variant has a key that isn't stored.".to_string()
+    } else {
+        format!("This always returns `Some`, because `{}` only has unit variants.", enum_name)
+    };
+    let get_doc = format!("Returns a reference to the value of the variant.\n\n{}", missing_doc);
+    let get_mut_doc = format!("Returns a mutable reference to the value of the variant.\n\n{}", missing_doc);
+    let get_unchecked_doc = if has_keys {
+        "Returns a reference to the value of the variant.\n\n# Panics\n\nPanics if the variant has a key that isn't stored."
+    } else {
+        "Returns a reference to the value of the variant."
+    };
+    let get_mut_unchecked_doc = match (has_keys, auto_insert) {
+        (true, false) => "Returns a mutable reference to the value of the variant.\n\n\
+            # Panics\n\nPanics if the variant has a key that isn't stored.",
+        (true, true) => "Returns a mutable reference to the value of the variant, inserting `T::default()` if it has a key \
+            that isn't stored.",
+        (false, _) => "Returns a mutable reference to the value of the variant."
+    };
+    let push_doc = if dense_idents.is_empty() {
+        "Stores a value for the variant, which replaces the old value unless the variant is `multi`."
+    } else {
+        "Stores a value for the variant, which replaces the old value unless the variant is `multi`.\n\n\
+            # Panics\n\nPanics if the key of a `dense_key` variant isn't below the maximum, or isn't one of its keys."
+    };
+
+    // `auto_insert` needs default values to insert
+    let auto_insert_bounds = if auto_insert {
+        quote! { where T: Default, #(#auto_insert_bounds),* }
+    } else {
+        quote! {}
+    };
+
+    let accessors = accessors(cx);
+    let methods = quote! {
+        #[doc = #get_unchecked_doc]
+        #[must_use]
+        #[track_caller]
+        pub fn get_unchecked(&self, var: &#enum_path) -> &T {
+            #get_unchecked_body
+        }
+
+        #[doc = #get_mut_unchecked_doc]
+        #[track_caller]
+        pub fn get_mut_unchecked(&mut self, var: &#enum_path) -> &mut T #auto_insert_bounds {
+            #get_mut_unchecked_body
+        }
+
+        #[doc = #get_doc]
+        #[must_use]
+        pub fn get(&self, var: &#enum_path) -> Option<&T> {
+            #get_body
+        }
+
+        #[doc = #get_mut_doc]
+        #[must_use]
+        pub fn get_mut(&mut self, var: &#enum_path) -> Option<&mut T> {
+            #get_mut_body
+        }
+
+        /// Returns the value of the variant, and the variant with its key as it's stored (which might be different
+        /// from the one that was looked up, if the keys are only equal).
+        #[must_use]
+        pub fn get_key_value(&self, var: &#enum_path) -> Option<(#enum_path, &T)> where #(#clone_bounds),* {
+            match #scrutinee {
+                #(#key_values),*
+            }
+        }
+
+        /// Returns the value of the unit variant whose field has the name, if there is one.
+        #[must_use]
+        pub fn get_by_name(&self, name: &str) -> Option<&T> {
+            match name {
+                #(#unit_names => Some(&#unit_name_places),)*
+                _ => None
+            }
+        }
+
+        /// Returns a mutable reference to the value of the unit variant whose field has the name, if there is one.
+        #[must_use]
+        pub fn get_by_name_mut(&mut self, name: &str) -> Option<&mut T> {
+            match name {
+                #(#unit_names => Some(&mut #unit_name_places),)*
+                _ => None
+            }
+        }
+
+        /// Returns the value of the first variant that has one.
+        #[must_use]
+        pub fn get_with_fallback<'v>(&self, vars: impl IntoIterator<Item = &'v #enum_path>) -> Option<&T> {
+            vars.into_iter().find_map(|var| self.get(var))
+        }
+
+        /// Returns the value of the variant, or the result of `fallback` if it doesn't have one.
+        #[must_use]
+        pub fn get_or_else<'s>(&'s self, var: &#enum_path, fallback: impl FnOnce() -> &'s T) -> &'s T {
+            self.get(var).unwrap_or_else(fallback)
+        }
+
+        /// Looks up each of the variants, returning their values in the same order.
+        #[must_use]
+        pub fn gather<'a>(&'a self, vars: impl IntoIterator<Item = #enum_path>) -> Vec<Option<&'a T>> {
+            vars.into_iter().map(|var| self.get(&var)).collect()
+        }
+
+        #[doc = #push_doc]
+        #[track_caller]
+        pub fn push(&mut self, var: #enum_path, value: #value_ty) {
+            #into_value
+            #push_body
+        }
+
+        /// Returns all of the values of the variant, which can only be more than one for `multi` variants.
+        #[must_use]
+        pub fn get_all(&self, var: &#enum_path) -> &[T] {
+            #get_all_body
+        }
+
+        #index_fn
+
+        #(#accessors)*
+    };
+    let items = quote! {
+    #derived
+    #impl_attrs
+    impl<'v, T: #(#bounds)+*> std::ops::Index<&'v #enum_path> for #struct_ident<T> {
+        type Output = T;
+
+        #[track_caller]
+        fn index(&self, var: &'v #enum_path) -> &T {
+            self.get_unchecked(var)
+        }
+    }
+
+    #derived
+    #impl_attrs
+    impl<'v, T: #(#bounds)+*> std::ops::IndexMut<&'v #enum_path> for #struct_ident<T> #auto_insert_bounds {
+        #[track_caller]
+        fn index_mut(&mut self, var: &'v #enum_path) -> &mut T {
+            self.get_mut_unchecked(var)
+        }
+    }
+
+    #derived
+    #impl_attrs
+    impl<T: #(#bounds)+*> std::ops::Index<#enum_path> for #struct_ident<T> {
+        type Output = T;
+
+        #[track_caller]
+        fn index(&self, var: #enum_path) -> &T {
+            self.get_unchecked(&var)
+        }
+    }
+
+    #derived
+    #impl_attrs
+    impl<T: #(#bounds)+*> std::ops::IndexMut<#enum_path> for #struct_ident<T> #auto_insert_bounds {
+        #[track_caller]
+        fn index_mut(&mut self, var: #enum_path) -> &mut T {
+            self.get_mut_unchecked(&var)
+        }
+    }
+    };
+    Part { methods, items }
+}
+
+/// Generates the accessors of the fields with keys, and the getters of the merged structs.
+fn accessors(cx: &Context) -> Vec<proc_macro2::TokenStream> {
+    let Context { value_ty, into_value, key_bound, .. } = cx;
+    let EnumAttrs { impl_fors, handlers, visitor, getter_table, .. } = cx.attrs;
+    let (visitor, getter_table) = (*visitor, *getter_table);
+    let mut accessors = vec![];
+    let mut method_names: std::collections::HashSet<String> = METHOD_NAMES.iter().map(|name| name.to_string()).collect();
+    method_names.extend(impl_fors.iter().flat_map(|impl_for| impl_for.extras.iter().map(Ident::to_string)));
+    if handlers.is_some() {
+        method_names.extend(["call", "try_call"].iter().map(|name| name.to_string()));
+    }
+    if visitor {
+        method_names.insert("accept".to_string());
+    }
+    if getter_table {
+        method_names.extend(["is_map_backed", "unit_index", "GETTERS", "GETTERS_MUT"].iter().map(|name| name.to_string()));
+    }
+    for (&Field { normal, snake, .. }, storage) in cx.stored() {
+        if let Storage::Value = storage {
+            continue;
+        }
+        let variant_name = format!("`{}::{}`", cx.enum_name, normal);
+        // the accessors are named `{field}`, `{field}_mut`, `{field}_insert`, `{field}_remove`, and `{field}_key_value`
+        let accessor_names = [
+            snake.clone(),
+            format_ident!("{}_mut", snake, span = snake.span()),
+            format_ident!("{}_insert", snake, span = snake.span()),
+            format_ident!("{}_remove", snake, span = snake.span()),
+            format_ident!("{}_key_value", snake, span = snake.span())
+        ];
+        // an accessor that would have the same name as another method is left out, so that enums with variants like
+        // `Get(i32)` still work
+        let kept_accessors = accessor_names.clone().map(|name| matches!(storage, Storage::Value) || method_names.insert(name.to_string()));
+        let [get_name, get_mut_name, insert_name, remove_name, key_value_name] = &accessor_names;
+        match storage {
+            Storage::Value => {}
+            Storage::Map(ty) => {
+                let get_doc = format!("Returns a reference to the value of {} with the key, if it's stored.", variant_name);
+                let get_mut_doc = format!("Returns a mutable reference to the value of {} with the key, if it's stored.", variant_name);
+                let insert_doc = format!("Stores the value of {} with the key, returning the old value.", variant_name);
+                let remove_doc = format!("Removes the value of {} with the key, returning it.", variant_name);
+                let key_value_doc = format!("Returns the stored key and the value of {} with the key, if it's stored.", variant_name);
+                let accessor_methods = [
+                    quote! {
+                        #[doc = #get_doc]
+                        #[must_use]
+                        pub fn #get_name<Q: #key_bound + ?Sized>(&self, key: &Q) -> Option<&T> where #ty: std::borrow::Borrow<Q> {
+                            self.#snake.get(key)
+                        }
+                    },
+                    quote! {
+                        #[doc = #get_mut_doc]
+                        #[must_use]
+                        pub fn #get_mut_name<Q: #key_bound + ?Sized>(&mut self, key: &Q) -> Option<&mut T> where #ty: std::borrow::Borrow<Q> {
+                            self.#snake.get_mut(key)
+                        }
+                    },
+                    quote! {
+                        #[doc = #insert_doc]
+                        pub fn #insert_name(&mut self, key: #ty, value: #value_ty) -> Option<T> {
+                            #into_value
+                            self.#snake.insert(key, value)
+                        }
+                    },
+                    quote! {
+                        #[doc = #remove_doc]
+                        pub fn #remove_name<Q: #key_bound + ?Sized>(&mut self, key: &Q) -> Option<T> where #ty: std::borrow::Borrow<Q> {
+                            self.#snake.remove(key)
+                        }
+                    },
+                    quote! {
+                        #[doc = #key_value_doc]
+                        #[must_use]
+                        pub fn #key_value_name<Q: #key_bound + ?Sized>(&self, key: &Q) -> Option<(&#ty, &T)> where #ty: std::borrow::Borrow<Q> {
+                            self.#snake.get_key_value(key)
+                        }
+                    }
+                ];
+                accessors.extend(kept_accessors.iter().zip(accessor_methods).filter_map(|(kept, method)| kept.then_some(method)));
+            }
+            Storage::Dense(ty, keys, _) => {
+                let key_index = keys.index(ty, quote! { *key });
+                let owned_index = keys.index(ty, quote! { key });
+                let bound = keys.bound();
+                let push_message = format!("dense key is not {}", bound);
+                let get_doc = format!("Returns a reference to the value of {} with the key, if it's stored.", variant_name);
+                let get_mut_doc = format!("Returns a mutable reference to the value of {} with the key, if it's stored.", variant_name);
+                let insert_doc = format!(
+                    "Stores the value of {} with the key, returning the old value.\n\n# Panics\n\nPanics if the key isn't {}.",
+                    variant_name, bound
+                );
+                let remove_doc = format!("Removes the value of {} with the key, returning it.", variant_name);
+                let accessor_methods = [
+                    quote! {
+                        #[doc = #get_doc]
+                        #[must_use]
+                        pub fn #get_name(&self, key: &#ty) -> Option<&T> {
+                            self.#snake.get(#key_index).and_then(Option::as_ref)
+                        }
+                    },
+                    quote! {
+                        #[doc = #get_mut_doc]
+                        #[must_use]
+                        pub fn #get_mut_name(&mut self, key: &#ty) -> Option<&mut T> {
+                            self.#snake.get_mut(#key_index).and_then(Option::as_mut)
+                        }
+                    },
+                    quote! {
+                        #[doc = #insert_doc]
+                        #[track_caller]
+                        pub fn #insert_name(&mut self, key: #ty, value: #value_ty) -> Option<T> {
+                            #into_value
+                            match self.#snake.get_mut(#owned_index) {
+                                Some(slot) => slot.replace(value),
+                                None => panic!(#push_message)
+                            }
+                        }
+                    },
+                    quote! {
+                        #[doc = #remove_doc]
+                        pub fn #remove_name(&mut self, key: &#ty) -> Option<T> {
+                            self.#snake.get_mut(#key_index).and_then(Option::take)
+                        }
+                    }
+                ];
+                accessors.extend(kept_accessors.iter().zip(accessor_methods).filter_map(|(kept, method)| kept.then_some(method)));
+            }
+            Storage::Multi(ty) => {
+                let get_doc = format!("Returns all of the values of {} with the key.", variant_name);
+                let get_mut_doc = format!("Returns a mutable reference to the values of {} with the key, if any are stored.", variant_name);
+                let insert_doc = format!("Adds a value of {} with the key, after the ones already stored.", variant_name);
+                let remove_doc = format!("Removes all of the values of {} with the key, returning them.", variant_name);
+                let key_value_doc = format!("Returns the stored key and all of the values of {} with the key, if any are stored.", variant_name);
+                let accessor_methods = [
+                    quote! {
+                        #[doc = #get_doc]
+                        #[must_use]
+                        pub fn #get_name<Q: #key_bound + ?Sized>(&self, key: &Q) -> &[T] where #ty: std::borrow::Borrow<Q> {
+                            self.#snake.get(key).map(Vec::as_slice).unwrap_or(&[])
+                        }
+                    },
+                    quote! {
+                        #[doc = #get_mut_doc]
+                        #[must_use]
+                        pub fn #get_mut_name<Q: #key_bound + ?Sized>(&mut self, key: &Q) -> Option<&mut Vec<T>> where #ty: std::borrow::Borrow<Q> {
+                            self.#snake.get_mut(key)
+                        }
+                    },
+                    quote! {
+                        #[doc = #insert_doc]
+                        pub fn #insert_name(&mut self, key: #ty, value: #value_ty) {
+                            #into_value
+                            self.#snake.entry(key).or_default().push(value)
+                        }
+                    },
+                    quote! {
+                        #[doc = #remove_doc]
+                        pub fn #remove_name<Q: #key_bound + ?Sized>(&mut self, key: &Q) -> Option<Vec<T>> where #ty: std::borrow::Borrow<Q> {
+                            self.#snake.remove(key)
+                        }
+                    },
+                    quote! {
+                        #[doc = #key_value_doc]
+                        #[must_use]
+                        pub fn #key_value_name<Q: #key_bound + ?Sized>(&self, key: &Q) -> Option<(&#ty, &[T])> where #ty: std::borrow::Borrow<Q> {
+                            self.#snake.get_key_value(key).map(|(key, values)| (key, values.as_slice()))
+                        }
+                    }
+                ];
+                accessors.extend(kept_accessors.iter().zip(accessor_methods).filter_map(|(kept, method)| kept.then_some(method)));
+            }
+        }
+    }
+
+    // merged enums are stored in their own variants structs, with getters named after them
+    for Merged { path: merged, snake, .. } in &cx.merged {
+        let get_name = format_ident!("get_{}", snake, span = snake.span());
+        let get_mut_name = format_ident!("get_{}_mut", snake, span = snake.span());
+        // like the accessors of variants with keys, a getter that would collide with another method is left out
+        let kept_getters = [&get_name, &get_mut_name].map(|name| method_names.insert(name.to_string()));
+        let merged_name = quote!(#merged).to_string().replace(' ', "");
+        let get_doc = format!("Returns a reference to the value of a variant of `{}`, from the merged struct.", merged_name);
+        let get_mut_doc = format!("Returns a mutable reference to the value of a variant of `{}`, from the merged struct.", merged_name);
+        let getter_methods = [
+            quote! {
+                #[doc = #get_doc]
+                #[must_use]
+                pub fn #get_name(&self, var: &#merged) -> Option<&T> {
+                    self.#snake.get(var)
+                }
+            },
+            quote! {
+                #[doc = #get_mut_doc]
+                #[must_use]
+                pub fn #get_mut_name(&mut self, var: &#merged) -> Option<&mut T> {
+                    self.#snake.get_mut(var)
+                }
+            }
+        ];
+        accessors.extend(kept_getters.iter().zip(getter_methods).filter_map(|(kept, method)| kept.then_some(method)));
+    }
+    accessors
+}
@@ -0,0 +1,541 @@
+//! The iterators over the struct, and the methods that convert it into its values.
+
+use quote::{quote, format_ident};
+
+use super::{Context, EnumAttrs, Field, Part, Storage};
+
+/// Generates `iter()`, `into_values()`, `drain()`, and the rest of the methods that go through the values, along with
+/// the iterator types that they return.
+pub(super) fn generate(cx: &Context) -> Part {
+    let Context {
+        visibility, enum_path, enum_name, value_idents, value_variants, deref, map_mod, derived, impl_attrs, helper_hidden, allow_casts,
+        clone_bounds, to_vec_bounds, ..
+    } = cx;
+    let EnumAttrs { struct_ident, bounds, catch_all, .. } = cx.attrs;
+    let jump_table = cx.jump_table;
+
+    let mut to_vec_values = vec![];
+    let mut to_vec_entries = vec![];
+    // the iterators yield the unit variants from an array, and then go through an iterator for each other field
+    // (which are kept in a separate iterator type, so that they can be iterated on their own)
+    let mut iter_values = vec![];
+    let mut iter_mut_values = vec![];
+    let mut iter_fields = vec![];
+    let mut iter_mut_fields = vec![];
+    let mut iter_inits = vec![];
+    let mut iter_mut_inits = vec![];
+    let mut iter_nexts = vec![];
+    let mut iter_mut_nexts = vec![];
+    let mut iter_map_indices = vec![];
+    let mut iter_dense_indices = vec![];
+    let mut iter_multi_indices = vec![];
+    let mut iter_dense_backs = vec![];
+    let mut iter_mut_dense_backs = vec![];
+    // the iterators of `into_values` and `drain` go through a field of the iterator for each field of the struct
+    let mut into_values_fields = vec![];
+    let mut into_values_inits = vec![];
+    let mut drain_fields = vec![];
+    let mut drain_inits = vec![];
+    let mut drain_nexts = vec![];
+    let mut drain_map_indices = vec![];
+    let mut drain_dense_indices = vec![];
+    let mut drain_multi_indices = vec![];
+    for (Field { snake, variant, place, .. }, storage) in cx.stored() {
+        // the value of a unit variant, as a place of type `T`
+        let unit = quote! { #deref self.#place };
+        match storage {
+            Storage::Value if jump_table => {
+                iter_values.push(quote! { (#variant, &self.#place) });
+                iter_mut_values.push(quote! { (#variant, #snake) });
+            }
+            Storage::Value => {
+                to_vec_values.push(quote! { vec.push((#variant, #unit)); });
+                iter_values.push(quote! { (#variant, &#unit) });
+                iter_mut_values.push(quote! { (#variant, &mut #unit) });
+            }
+            Storage::Map(ty) => {
+                to_vec_entries.push(quote! {
+                    vec.extend(self.#snake.into_iter().map(|(key, value)| (#variant, value)));
+                });
+                let index = syn::Index::from(iter_fields.len());
+                iter_fields.push(quote! { #map_mod::Iter<'a, #ty, T> });
+                iter_mut_fields.push(quote! { #map_mod::IterMut<'a, #ty, T> });
+                iter_inits.push(quote! { self.#snake.iter() });
+                iter_mut_inits.push(quote! { self.#snake.iter_mut() });
+                let next = quote! {
+                    self.#index.next().map(|(key, value)| {
+                        let key = key.clone();
+                        (#variant, value)
+                    })
+                };
+                iter_nexts.push(next.clone());
+                iter_mut_nexts.push(next);
+                iter_map_indices.push(index);
+                into_values_fields.push(quote! { #map_mod::IntoValues<#ty, T> });
+                into_values_inits.push(quote! { self.#snake.into_values() });
+                let index = syn::Index::from(drain_fields.len());
+                drain_fields.push(cx.map_drain_ty(ty, quote! { T }));
+                drain_inits.push(cx.map_drain(snake));
+                drain_nexts.push(quote! { self.#index.next().map(|(key, value)| (#variant, value)) });
+                drain_map_indices.push(index);
+            }
+            Storage::Dense(ty, keys, array) => {
+                let array = *array;
+                let empty_slots = keys.empty_slots(array);
+                let owned_iter_ty = keys.owned_iter_ty(array);
+                let key_at = keys.key(ty);
+                to_vec_entries.push(quote! {
+                    vec.extend(IntoIterator::into_iter(self.#snake).enumerate().filter_map(
+                        |(index, value)| value.map(|value| {
+                            let key = #key_at;
+                            (#variant, value)
+                        })
+                    ));
+                });
+                let index = syn::Index::from(iter_fields.len());
+                iter_fields.push(quote! { std::iter::Enumerate<std::slice::Iter<'a, Option<T>>> });
+                iter_mut_fields.push(quote! { std::iter::Enumerate<std::slice::IterMut<'a, Option<T>>> });
+                iter_inits.push(quote! { self.#snake.iter().enumerate() });
+                iter_mut_inits.push(quote! { self.#snake.iter_mut().enumerate() });
+                iter_nexts.push(quote! {
+                    self.#index.by_ref().find_map(|(index, value)| value.as_ref().map(|value| {
+                        let key = #key_at;
+                        (#variant, value)
+                    }))
+                });
+                iter_mut_nexts.push(quote! {
+                    self.#index.by_ref().find_map(|(index, value)| value.as_mut().map(|value| {
+                        let key = #key_at;
+                        (#variant, value)
+                    }))
+                });
+                iter_dense_backs.push(quote! {
+                    self.#index.by_ref().rev().find_map(|(index, value)| value.as_ref().map(|value| {
+                        let key = #key_at;
+                        (#variant, value)
+                    }))
+                });
+                iter_mut_dense_backs.push(quote! {
+                    self.#index.by_ref().rev().find_map(|(index, value)| value.as_mut().map(|value| {
+                        let key = #key_at;
+                        (#variant, value)
+                    }))
+                });
+                iter_dense_indices.push(index);
+                into_values_fields.push(quote! { std::iter::Flatten<#owned_iter_ty> });
+                into_values_inits.push(quote! { IntoIterator::into_iter(self.#snake).flatten() });
+                let index = syn::Index::from(drain_fields.len());
+                drain_fields.push(quote! { std::iter::Enumerate<#owned_iter_ty> });
+                // the slots are swapped out right away, so they're all empty even if the iterator isn't used up
+                drain_inits.push(quote! {
+                    IntoIterator::into_iter(std::mem::replace(&mut self.#snake, #empty_slots)).enumerate()
+                });
+                drain_nexts.push(quote! {
+                    self.#index.by_ref().find_map(|(index, value)| value.map(|value| {
+                        let key = #key_at;
+                        (#variant, value)
+                    }))
+                });
+                drain_dense_indices.push(index);
+            }
+            Storage::Multi(ty) => {
+                to_vec_entries.push(quote! {
+                    vec.extend(self.#snake.into_iter().flat_map(|(key, values)| values.into_iter().map(move |value| {
+                        let key = key.clone();
+                        (#variant, value)
+                    })));
+                });
+                // each multi field is iterated with its hashmap iterator, and an iterator over the values of the current key
+                let index = syn::Index::from(iter_fields.len());
+                iter_fields.push(quote! {
+                    (#map_mod::Iter<'a, #ty, Vec<T>>, Option<(&'a #ty, std::slice::Iter<'a, T>)>)
+                });
+                iter_mut_fields.push(quote! {
+                    (#map_mod::IterMut<'a, #ty, Vec<T>>, Option<(&'a #ty, std::slice::IterMut<'a, T>)>)
+                });
+                iter_inits.push(quote! { (self.#snake.iter(), None) });
+                iter_mut_inits.push(quote! { (self.#snake.iter_mut(), None) });
+                for (nexts, values_iter) in [(&mut iter_nexts, quote! { iter }), (&mut iter_mut_nexts, quote! { iter_mut })] {
+                    nexts.push(quote! {
+                        loop {
+                            let current = self.#index.1.as_mut().and_then(|(key, values)| values.next().map(|value| (*key, value)));
+                            if let Some((key, value)) = current {
+                                let key = key.clone();
+                                break Some((#variant, value));
+                            }
+                            match self.#index.0.next() {
+                                Some((key, values)) => self.#index.1 = Some((key, values.#values_iter())),
+                                None => break None
+                            }
+                        }
+                    });
+                }
+                iter_multi_indices.push(index);
+                into_values_fields.push(quote! { std::iter::Flatten<#map_mod::IntoValues<#ty, Vec<T>>> });
+                into_values_inits.push(quote! { self.#snake.into_values().flatten() });
+                // like iterating, the values of the current key are drained separately
+                let index = syn::Index::from(drain_fields.len());
+                let drain_ty = cx.map_drain_ty(ty, quote! { Vec<T> });
+                drain_fields.push(quote! {
+                    (#drain_ty, Option<(#ty, std::vec::IntoIter<T>)>)
+                });
+                let drain_init = cx.map_drain(snake);
+                drain_inits.push(quote! { (#drain_init, None) });
+                drain_nexts.push(quote! {
+                    loop {
+                        let current = self.#index.1.as_mut().and_then(|(key, values)| values.next().map(|value| (key.clone(), value)));
+                        if let Some((key, value)) = current {
+                            break Some((#variant, value));
+                        }
+                        match self.#index.0.next() {
+                            Some((key, values)) => self.#index.1 = Some((key, values.into_iter())),
+                            None => break None
+                        }
+                    }
+                });
+                drain_multi_indices.push(index);
+            }
+        }
+    }
+    if jump_table {
+        to_vec_values.push(quote! {
+            vec.extend(IntoIterator::into_iter([#(#value_variants),*]).zip(IntoIterator::into_iter(self.values)));
+        });
+    }
+    if let Some(catch_all) = catch_all {
+        to_vec_entries.push(quote! { vec.extend(self.#catch_all); });
+        let index = syn::Index::from(iter_fields.len());
+        iter_fields.push(quote! { #map_mod::Iter<'a, #enum_path, T> });
+        iter_mut_fields.push(quote! { #map_mod::IterMut<'a, #enum_path, T> });
+        iter_inits.push(quote! { self.#catch_all.iter() });
+        iter_mut_inits.push(quote! { self.#catch_all.iter_mut() });
+        let next = quote! { self.#index.next().map(|(var, value)| (var.clone(), value)) };
+        iter_nexts.push(next.clone());
+        iter_mut_nexts.push(next);
+        iter_map_indices.push(index);
+        into_values_fields.push(quote! { #map_mod::IntoValues<#enum_path, T> });
+        into_values_inits.push(quote! { self.#catch_all.into_values() });
+        let index = syn::Index::from(drain_fields.len());
+        drain_fields.push(cx.map_drain_ty(enum_path, quote! { T }));
+        drain_inits.push(cx.map_drain(catch_all));
+        drain_nexts.push(quote! { self.#index.next() });
+        drain_map_indices.push(index);
+    }
+
+    // the mutable iterators over the unit variants can't borrow from a jump table's array more than once
+    let unit_mut_binding = if jump_table {
+        quote! { let [#(#value_idents),*] = &mut self.values; }
+    } else {
+        quote! {}
+    };
+    let unit_values = if jump_table {
+        quote! { self.values }
+    } else {
+        quote! { [#(#deref self.#value_idents),*] }
+    };
+
+    let value_count = value_idents.len();
+    let to_vec_body = if to_vec_values.is_empty() && to_vec_entries.is_empty() {
+        quote! { Vec::new() }
+    } else {
+        quote! {
+            let mut vec = Vec::with_capacity(self.len());
+            #(#to_vec_values)*
+            #(#to_vec_entries)*
+            vec
+        }
+    };
+
+    let iter_ident = format_ident!("{}Iter", struct_ident);
+    let iter_mut_ident = format_ident!("{}IterMut", struct_ident);
+    let map_iter_ident = format_ident!("{}MapIter", struct_ident);
+    let map_iter_mut_ident = format_ident!("{}MapIterMut", struct_ident);
+
+    // the iterators know their exact length unless they have to skip empty slots of dense fields or go through the
+    // values of multi fields, and can only be iterated from the back if there are no hashmaps.
+    let exact_size = iter_dense_indices.is_empty() && iter_multi_indices.is_empty();
+    let double_ended = iter_map_indices.is_empty() && iter_multi_indices.is_empty();
+    let map_upper = if iter_multi_indices.is_empty() {
+        quote! { Some(len #(+ self.#iter_dense_indices.len())*) }
+    } else {
+        quote! { None }
+    };
+
+    // `into_values` has its own iterator, with the unit values in an array followed by a field for each other field
+    let into_values_ident = format_ident!("{}IntoValues", struct_ident);
+    let into_values_doc = format!("An iterator over the values of a `{}`, without their variants.", struct_ident);
+    let into_values_indices: Vec<_> = (1..=into_values_fields.len()).map(syn::Index::from).collect();
+    let into_values_backs = into_values_indices.iter().rev();
+    let mut into_values_items = quote! {
+        #[doc = #into_values_doc]
+        #[must_use = "iterators are lazy and do nothing unless consumed"]
+        #helper_hidden
+        #visibility struct #into_values_ident<T>(std::array::IntoIter<T, #value_count>, #(#into_values_fields),*);
+
+        #derived
+        #impl_attrs
+        impl<T> Iterator for #into_values_ident<T> {
+            type Item = T;
+
+            fn next(&mut self) -> Option<T> {
+                self.0.next() #(.or_else(|| self.#into_values_indices.next()))*
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                let (mut lower, mut upper) = (self.0.len(), Some(self.0.len()));
+                #(
+                    let (field_lower, field_upper) = self.#into_values_indices.size_hint();
+                    lower = lower.saturating_add(field_lower);
+                    upper = upper.zip(field_upper).and_then(|(upper, field_upper)| upper.checked_add(field_upper));
+                )*
+                (lower, upper)
+            }
+        }
+
+        #derived
+        #impl_attrs
+        impl<T> std::iter::FusedIterator for #into_values_ident<T> {}
+    };
+    if into_values_fields.is_empty() {
+        into_values_items.extend(quote! {
+            #derived
+            #impl_attrs
+            impl<T> ExactSizeIterator for #into_values_ident<T> {}
+        });
+    }
+    if double_ended {
+        into_values_items.extend(quote! {
+            #derived
+            #impl_attrs
+            impl<T> DoubleEndedIterator for #into_values_ident<T> {
+                fn next_back(&mut self) -> Option<T> {
+                    None #(.or_else(|| self.#into_values_backs.next_back()))*.or_else(|| self.0.next_back())
+                }
+            }
+        });
+    }
+
+    // `drain` has a field for each field of the struct with keys, which are all emptied when it's created
+    let drain_ident = format_ident!("{}Drain", struct_ident);
+    let drain_doc = format!("A draining iterator over the variants of `{}` with keys and their values.", enum_name);
+    let drain_next = match drain_nexts.split_first() {
+        Some((first, rest)) => quote! { #first #(.or_else(|| #rest))* },
+        None => quote! { None }
+    };
+    let drain_upper = if drain_multi_indices.is_empty() {
+        quote! { Some(len #(+ self.#drain_dense_indices.len())*) }
+    } else {
+        quote! { None }
+    };
+    let drain_items = quote! {
+        #[doc = #drain_doc]
+        #helper_hidden
+        #visibility struct #drain_ident<'a, T>(#(#drain_fields,)* std::marker::PhantomData<&'a mut T>);
+
+        // dense keys are converted to and from indices with `as`, even if they already are `usize`
+        #allow_casts
+        #derived
+        #impl_attrs
+        impl<'a, T> Iterator for #drain_ident<'a, T> where #(#to_vec_bounds),* {
+            type Item = (#enum_path, T);
+
+            fn next(&mut self) -> Option<Self::Item> {
+                #drain_next
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                let len = 0 #(+ self.#drain_map_indices.len())*;
+                let lower = len #(+ self.#drain_multi_indices.1.as_ref().map_or(0, |(_, values)| values.len()))*;
+                (lower, #drain_upper)
+            }
+        }
+
+        #derived
+        #impl_attrs
+        impl<'a, T> std::iter::FusedIterator for #drain_ident<'a, T> where #(#to_vec_bounds),* {}
+    };
+    let mut iter_items = vec![];
+    for (ident, map_ident, reference, fields, nexts, backs, kind) in [
+        (&iter_ident, &map_iter_ident, quote! { &'a T }, &iter_fields, &iter_nexts, &iter_dense_backs, "references"),
+        (&iter_mut_ident, &map_iter_mut_ident, quote! { &'a mut T }, &iter_mut_fields, &iter_mut_nexts, &iter_mut_dense_backs, "mutable references")
+    ] {
+        let iter_doc = format!("An iterator over the variants of `{}` and {} to their values.", enum_name, kind);
+        let map_iter_doc = format!("An iterator over the variants of `{}` with keys and {} to their values.", enum_name, kind);
+        let map_next = match nexts.split_first() {
+            Some((first, rest)) => quote! { #first #(.or_else(|| #rest))* },
+            None => quote! { None }
+        };
+        let mut map_back = quote! { None };
+        for back in backs {
+            map_back = quote! { #back.or_else(|| #map_back) };
+        }
+        iter_items.push(quote! {
+            #[doc = #iter_doc]
+            #[must_use = "iterators are lazy and do nothing unless consumed"]
+            #helper_hidden
+            #visibility struct #ident<'a, T>(std::array::IntoIter<(#enum_path, #reference), #value_count>, #map_ident<'a, T>);
+
+            #derived
+            #impl_attrs
+            impl<'a, T> Iterator for #ident<'a, T> where #(#clone_bounds),* {
+                type Item = (#enum_path, #reference);
+
+                fn next(&mut self) -> Option<Self::Item> {
+                    self.0.next().or_else(|| self.1.next())
+                }
+
+                fn size_hint(&self) -> (usize, Option<usize>) {
+                    let (lower, upper) = self.1.size_hint();
+                    (lower + self.0.len(), upper.map(|upper| upper + self.0.len()))
+                }
+            }
+
+            #derived
+            #impl_attrs
+            impl<'a, T> std::iter::FusedIterator for #ident<'a, T> where #(#clone_bounds),* {}
+
+            #[doc = #map_iter_doc]
+            #[must_use = "iterators are lazy and do nothing unless consumed"]
+            #helper_hidden
+            #visibility struct #map_ident<'a, T>(#(#fields,)* std::marker::PhantomData<#reference>);
+
+            // dense keys are converted to and from indices with `as`, even if they already are `usize`
+            #allow_casts
+            #derived
+            #impl_attrs
+            impl<'a, T> Iterator for #map_ident<'a, T> where #(#clone_bounds),* {
+                type Item = (#enum_path, #reference);
+
+                fn next(&mut self) -> Option<Self::Item> {
+                    #map_next
+                }
+
+                fn size_hint(&self) -> (usize, Option<usize>) {
+                    let len = 0 #(+ self.#iter_map_indices.len())*;
+                    let lower = len #(+ self.#iter_multi_indices.1.as_ref().map_or(0, |(_, values)| values.len()))*;
+                    (lower, #map_upper)
+                }
+            }
+
+            #derived
+            #impl_attrs
+            impl<'a, T> std::iter::FusedIterator for #map_ident<'a, T> where #(#clone_bounds),* {}
+        });
+        if exact_size {
+            iter_items.push(quote! {
+                #derived
+                #impl_attrs
+                impl<'a, T> ExactSizeIterator for #ident<'a, T> where #(#clone_bounds),* {}
+                #derived
+                #impl_attrs
+                impl<'a, T> ExactSizeIterator for #map_ident<'a, T> where #(#clone_bounds),* {}
+            });
+        }
+        if double_ended {
+            iter_items.push(quote! {
+                #derived
+                #impl_attrs
+                impl<'a, T> DoubleEndedIterator for #ident<'a, T> where #(#clone_bounds),* {
+                    fn next_back(&mut self) -> Option<Self::Item> {
+                        self.1.next_back().or_else(|| self.0.next_back())
+                    }
+                }
+
+                #allow_casts
+                #derived
+                #impl_attrs
+                impl<'a, T> DoubleEndedIterator for #map_ident<'a, T> where #(#clone_bounds),* {
+                    fn next_back(&mut self) -> Option<Self::Item> {
+                        #map_back
+                    }
+                }
+            });
+        }
+    }
+
+    let methods = quote! {
+        /// Converts the struct into the variants and their values.
+        #[must_use]
+        pub fn to_vec(self) -> Vec<(#enum_path, T)> where #(#to_vec_bounds),* {
+            #to_vec_body
+        }
+
+        /// Converts the struct into its values, without the variants.
+        pub fn into_values(self) -> #into_values_ident<T> {
+            #into_values_ident(IntoIterator::into_iter(#unit_values), #(#into_values_inits),*)
+        }
+
+        /// Removes the values of all of the variants with keys, and returns them along with their variants. The unit
+        /// variants are left as they are.
+        ///
+        /// Like `HashMap::drain`, the values are removed even if the iterator is dropped before it's used up.
+        pub fn drain(&mut self) -> #drain_ident<'_, T> where #(#to_vec_bounds),* {
+            #drain_ident(#(#drain_inits,)* std::marker::PhantomData)
+        }
+
+        /// Returns an iterator over the variants and their values.
+        pub fn iter(&self) -> #iter_ident<'_, T> {
+            #iter_ident(self.iter_unit(), self.iter_map())
+        }
+
+        /// Returns an iterator over the variants and mutable references to their values.
+        pub fn iter_mut(&mut self) -> #iter_mut_ident<'_, T> {
+            #unit_mut_binding
+            #iter_mut_ident(
+                IntoIterator::into_iter([#(#iter_mut_values),*]),
+                #map_iter_mut_ident(#(#iter_mut_inits,)* std::marker::PhantomData)
+            )
+        }
+
+        /// Returns an iterator over the unit variants and their values.
+        pub fn iter_unit(&self) -> std::array::IntoIter<(#enum_path, &T), #value_count> {
+            IntoIterator::into_iter([#(#iter_values),*])
+        }
+
+        /// Returns an iterator over the unit variants and mutable references to their values.
+        pub fn iter_unit_mut(&mut self) -> std::array::IntoIter<(#enum_path, &mut T), #value_count> {
+            #unit_mut_binding
+            IntoIterator::into_iter([#(#iter_mut_values),*])
+        }
+
+        /// Returns an iterator over the variants with keys and their values.
+        pub fn iter_map(&self) -> #map_iter_ident<'_, T> {
+            #map_iter_ident(#(#iter_inits,)* std::marker::PhantomData)
+        }
+
+        /// Returns an iterator over the variants with keys and mutable references to their values.
+        pub fn iter_map_mut(&mut self) -> #map_iter_mut_ident<'_, T> {
+            #map_iter_mut_ident(#(#iter_mut_inits,)* std::marker::PhantomData)
+        }
+    };
+    let items = quote! {
+        #(#iter_items)*
+
+        #into_values_items
+
+        #drain_items
+
+    #derived
+    #impl_attrs
+    impl<'a, T: #(#bounds)+*> IntoIterator for &'a #struct_ident<T> where #(#clone_bounds),* {
+        type Item = (#enum_path, &'a T);
+        type IntoIter = #iter_ident<'a, T>;
+
+        fn into_iter(self) -> Self::IntoIter {
+            self.iter()
+        }
+    }
+
+    #derived
+    #impl_attrs
+    impl<'a, T: #(#bounds)+*> IntoIterator for &'a mut #struct_ident<T> where #(#clone_bounds),* {
+        type Item = (#enum_path, &'a mut T);
+        type IntoIter = #iter_mut_ident<'a, T>;
+
+        fn into_iter(self) -> Self::IntoIter {
+            self.iter_mut()
+        }
+    }
+    };
+    Part { methods, items }
+}
@@ -0,0 +1,490 @@
+//! Generates the variants struct of an enum.
+//!
+//! `expand` reads the attributes and variants of the enum into a [`Context`], which is what the submodules generate
+//! their part of the struct from. Nothing here depends on `proc_macro`, so that `benches/expand.rs` can include this
+//! module and run it.
+
+use syn::{Ident, ItemEnum, ext::IdentExt};
+use quote::{quote, format_ident};
+use inflector::Inflector;
+use proc_macro_error::emit_error;
+use check_keyword::CheckKeyword;
+
+mod attrs;
+mod variants;
+mod storage;
+mod getters;
+mod iters;
+mod traverse;
+mod features;
+
+pub(crate) use attrs::parse_ident_lit;
+use attrs::EnumAttrs;
+use variants::{Storage, VariantInfo, PrefillKeys};
+
+/// Generates the variants struct and impl for `input`, which is referred to by `enum_path` in the generated code.
+///
+/// With `augment_enum`, the enum also gets constructors for the struct.
+pub(crate) fn expand(input: &ItemEnum, enum_path: &syn::Path, augment_enum: bool) -> proc_macro2::TokenStream {
+    let attrs = EnumAttrs::read(input);
+    let vars = variants::read(input, enum_path, &attrs);
+    let cx = Context::new(input, enum_path, &attrs, &vars);
+
+    let storage = storage::generate(&cx);
+    let getters = getters::generate(&cx);
+    let iters = iters::generate(&cx);
+    let traverse = traverse::generate(&cx);
+    let getter_table = features::getter_table(&cx);
+    let Context { allow_casts, allow_args, case_lint, impl_attrs, .. } = &cx;
+    let EnumAttrs { struct_ident, bounds, .. } = &attrs;
+
+    let mut tokens = storage::key_assertions(&cx);
+    // the items of the attributes that add to the struct
+    tokens.extend(features::repr_helpers(&cx));
+    if augment_enum {
+        tokens.extend(features::enum_constructors(&cx));
+    }
+    tokens.extend(features::rows(&cx));
+    tokens.extend(features::handlers(&cx));
+    tokens.extend(features::for_each_macro(&cx));
+    tokens.extend(features::visitor(&cx));
+    tokens.extend(features::markers(&cx));
+    tokens.extend(features::cell(&cx));
+    tokens.extend(features::async_locks(&cx));
+    tokens.extend(features::impl_for(&cx));
+    tokens.extend(features::cross_eq(&cx));
+    tokens.extend(features::random(&cx));
+    tokens.extend(features::tables(&cx));
+    tokens.extend(features::serde_pairs(&cx));
+
+    // the struct, with each part's methods in its main impl
+    let Part { methods: storage_methods, items: storage_items } = storage;
+    let Part { methods: iter_methods, items: iter_items } = iters;
+    let Part { methods: traverse_methods, items: traverse_items } = traverse;
+    let Part { methods: getter_methods, items: getter_items } = getters;
+    tokens.extend(quote! {
+        #storage_items
+
+        #allow_casts
+        #allow_args
+        #case_lint
+        #impl_attrs
+        impl<T: #(#bounds)+*> #struct_ident<T> {
+            #storage_methods
+
+            #iter_methods
+
+            #traverse_methods
+
+            #getter_methods
+
+            #getter_table
+        }
+
+        #iter_items
+
+        #traverse_items
+
+        #getter_items
+    });
+    tokens
+}
+
+/// The code generated for one part of the struct: the methods that go in its main impl, and the items next to it.
+struct Part {
+    methods: proc_macro2::TokenStream,
+    items: proc_macro2::TokenStream
+}
+
+/// A variant of the enum, as it is stored in the struct.
+struct Field<'a> {
+    normal: &'a Ident,
+    snake: &'a Ident,
+    /// Constructs or matches the variant, like `VariantInfo::variant`.
+    variant: &'a proc_macro2::TokenStream,
+    /// `None` if the variant isn't supported, or if another variant already generates its field.
+    storage: Option<&'a Storage>,
+    prefill: Option<&'a PrefillKeys>,
+    /// Where the value of a unit variant is in `self`, which is in the `values` array of a jump table.
+    place: proc_macro2::TokenStream
+}
+
+/// An enum from `merge_with`, whose variants struct is stored in a field.
+struct Merged<'a> {
+    path: &'a syn::Path,
+    merged_struct: &'a syn::Path,
+    snake: Ident
+}
+
+/// Everything that the parts of the struct are generated from.
+struct Context<'a> {
+    input: &'a ItemEnum,
+    enum_path: &'a syn::Path,
+    visibility: &'a syn::Visibility,
+    /// How the enum is named in the generated docs.
+    enum_name: String,
+    /// Inherent impls can only be added to local enums.
+    is_local: bool,
+    attrs: &'a EnumAttrs,
+    /// All of the variants, including those that don't get a field.
+    vars: &'a [VariantInfo<'a>],
+    /// The variants in the order of the fields.
+    fields: Vec<Field<'a>>,
+    merged: Vec<Merged<'a>>,
+    /// The derives of the struct, without `Default` if it's implemented with `new_default()` for `prefill_keys`.
+    derives: Vec<&'a syn::Path>,
+    /// Whether `jump_table` and `boxed_fields` are given, and can be used.
+    jump_table: bool,
+    boxed_fields: bool,
+    /// `T: Default`, if `prefill_keys` fills in any keys with `T::default()`.
+    prefill_bounds: Vec<proc_macro2::TokenStream>,
+    derived: proc_macro2::TokenStream,
+    impl_attrs: proc_macro2::TokenStream,
+    case_lint: proc_macro2::TokenStream,
+    struct_hidden: proc_macro2::TokenStream,
+    helper_hidden: proc_macro2::TokenStream,
+    allow_casts: proc_macro2::TokenStream,
+    allow_args: proc_macro2::TokenStream,
+    /// Goes before the value of a unit variant, which is boxed with `boxed_fields`.
+    deref: proc_macro2::TokenStream,
+    serde_skip: proc_macro2::TokenStream,
+    /// The type of the values taken by the constructors and setters, and the statement that converts them to `T`.
+    value_ty: proc_macro2::TokenStream,
+    into_value: proc_macro2::TokenStream,
+    /// The maps that fields with keys are stored in, and the bound on their keys.
+    map_ty: proc_macro2::TokenStream,
+    map_mod: proc_macro2::TokenStream,
+    key_bound: proc_macro2::TokenStream,
+    map_with_capacity: proc_macro2::TokenStream,
+    /// The fields of the unit variants, how their values are accessed from `self`, and the variants themselves.
+    value_idents: Vec<&'a Ident>,
+    value_places: Vec<proc_macro2::TokenStream>,
+    value_variants: Vec<&'a proc_macro2::TokenStream>,
+    /// The fields stored in hashmaps (including the catch-all field), in dense storage, and in hashmaps of `Vec`s.
+    map_idents: Vec<&'a Ident>,
+    dense_idents: Vec<&'a Ident>,
+    multi_idents: Vec<&'a Ident>,
+    /// The iterators have to clone keys to build the variants. These bounds have a (pointless) lifetime binder, so that
+    /// keys that aren't `Clone` only make the iterators unusable instead of failing to compile.
+    clone_bounds: Vec<proc_macro2::TokenStream>,
+    /// `to_vec` only has to clone the keys of multi fields, once for each value.
+    to_vec_bounds: Vec<proc_macro2::TokenStream>
+}
+
+impl<'a> Context<'a> {
+    /// Checks the attributes against each other and the variants, and finds the fields.
+    fn new(input: &'a ItemEnum, enum_path: &'a syn::Path, attrs: &'a EnumAttrs, vars: &'a [VariantInfo<'a>]) -> Context<'a> {
+        let EnumAttrs { catch_all, cell, derives, .. } = attrs;
+        let enum_name = enum_path.segments.iter().map(|segment| segment.ident.to_string()).collect::<Vec<_>>().join("::");
+
+        // lints about the generated code are allowed, unless they're kept with `keep_lints`
+        let allow = |lints: proc_macro2::TokenStream| if attrs.keep_lints {
+            quote! {}
+        } else {
+            quote! { #[allow(#lints)] }
+        };
+        let struct_hidden = if attrs.hide_struct { quote! { #[doc(hidden)] } } else { quote! {} };
+        let helper_hidden = if attrs.hide_helpers { quote! { #[doc(hidden)] } } else { quote! {} };
+        let derived = if attrs.keep_lints { quote! {} } else { quote! { #[automatically_derived] } };
+        let impl_attrs = &attrs.impl_attrs;
+        let impl_attrs = quote! { #(#[#impl_attrs])* };
+        let allow_casts = allow(quote! { clippy::unnecessary_cast });
+        // `new` takes one argument per unit variant
+        let allow_args = allow(quote! { clippy::too_many_arguments });
+        // the fields (and the variables bound to them) might not be in snake case because of `preserve_case`,
+        // `rename_fields`, or `field_name`
+        let is_snake = |ident: &Ident| !ident.unraw().to_string().chars().any(char::is_uppercase);
+        let case_lint = if vars.iter().all(|var| is_snake(&var.snake)) && catch_all.iter().all(is_snake) {
+            quote! {}
+        } else {
+            allow(quote! { non_snake_case })
+        };
+
+        // a jump table stores all of the values in one array, so it only works if every variant is a unit variant
+        let jump_table = match &attrs.jump_table {
+            Some(path) if catch_all.is_some() || vars.iter().any(|var| !matches!(var.storage, Some(Storage::Value))) => {
+                emit_error!(path, "`jump_table` can only be used on enums with only unit variants, and without `catch_all`");
+                false
+            }
+            jump_table => jump_table.is_some()
+        };
+
+        // with `boxed_fields`, the values of unit variants are kept in boxes, and every use of them goes through `deref`
+        let boxed_fields = match &attrs.boxed_fields {
+            Some(path) if jump_table => {
+                emit_error!(path, "`boxed_fields` can't be combined with `jump_table`");
+                false
+            }
+            boxed_fields => boxed_fields.is_some()
+        };
+        let deref = if boxed_fields { quote! { * } } else { quote! {} };
+
+        // with `no_alloc`, every value has to be stored inline, so anything that would need the heap is refused
+        if let Some(path) = &attrs.no_alloc {
+            for var in vars {
+                match &var.storage {
+                    Some(Storage::Map(_)) => emit_error!(
+                        var.normal, "`{}` would be stored in a `HashMap`, which `no_alloc` doesn't allow", var.normal;
+                        help = "store it inline with `#[dense_key(max = ..., array)]` or `#[dense_key(keys(...))]`"
+                    ),
+                    Some(Storage::Dense(_, _, false)) => emit_error!(
+                        var.normal, "`{}` would be stored in a `Vec`, which `no_alloc` doesn't allow", var.normal;
+                        help = "store it inline by adding `array` to its `dense_key` attribute"
+                    ),
+                    Some(Storage::Multi(_)) => emit_error!(
+                        var.normal, "`{}` would be stored in a `HashMap` of `Vec`s, which `no_alloc` doesn't allow", var.normal;
+                        help = "a `dense_key` with `array` stores one value per key inline"
+                    ),
+                    _ => {}
+                }
+            }
+            if let Some(catch_all) = catch_all {
+                emit_error!(catch_all, "the catch-all field would be a `HashMap`, which `no_alloc` doesn't allow");
+                emit_error!(path, "`no_alloc` is given here");
+            }
+            if let Some(cell) = cell {
+                emit_error!(cell, "`{}` would keep the struct in an `Arc`, which `no_alloc` doesn't allow", cell);
+                emit_error!(path, "`no_alloc` is given here");
+            }
+            if boxed_fields {
+                emit_error!(path, "`no_alloc` can't be combined with `boxed_fields`");
+            }
+        }
+
+        // with `serde_skip_maps`, the hashmaps are left out of serialization, which only works if serde is derived
+        let serde_skip = match &attrs.serde_skip_maps {
+            Some(path) => {
+                let is_serde = |derive: &syn::Path| derive.segments.last()
+                    .is_some_and(|segment| segment.ident == "Serialize" || segment.ident == "Deserialize");
+                if !derives.iter().any(is_serde) {
+                    emit_error!(path, "`serde_skip_maps` needs `Serialize` or `Deserialize` in `struct_derive`");
+                }
+                let default = if attrs.ordered_maps { "std::collections::BTreeMap::new" } else { "std::collections::HashMap::new" };
+                quote! { #[serde(skip, default = #default)] }
+            }
+            None => quote! {}
+        };
+
+        // with `into_args`, the constructors and setters take anything that converts into `T`
+        let (value_ty, into_value) = if attrs.into_args {
+            (quote! { impl Into<T> }, quote! { let value = value.into(); })
+        } else {
+            (quote! { T }, quote! {})
+        };
+
+        // with `ordered_maps`, the variants with keys are stored in `BTreeMap`s, which always go through their keys in order.
+        // they can't reserve capacity, and are drained by taking the whole map.
+        let (map_ty, map_mod, key_bound, map_with_capacity) = if attrs.ordered_maps {
+            (
+                quote! { std::collections::BTreeMap },
+                quote! { std::collections::btree_map },
+                quote! { Ord },
+                quote! { std::collections::BTreeMap::new() }
+            )
+        } else {
+            (
+                quote! { std::collections::HashMap },
+                quote! { std::collections::hash_map },
+                quote! { std::hash::Hash + Eq },
+                quote! { std::collections::HashMap::with_capacity(map_capacity) }
+            )
+        };
+
+        // two variants generating the same field would only be caught inside the generated struct,
+        // so report both of them here and drop the later one.
+        let mut used_names: std::collections::HashMap<String, (&Ident, &Ident)> = std::collections::HashMap::new();
+        let mut value_count = 0;
+        let mut fields = vec![];
+        for VariantInfo { normal, snake, variant, storage, prefill } in vars {
+            let storage = match storage {
+                Some(storage) => match used_names.get(&snake.to_string()) {
+                    Some((first_normal, first_snake)) => {
+                        emit_error!(
+                            snake, "variants `{}` and `{}` both generate the field `{}`", first_normal, normal, snake;
+                            help = "rename one of them with `#[field_name = \"...\"]`"
+                        );
+                        emit_error!(first_snake, "field `{}` is first generated here", snake);
+                        None
+                    }
+                    None => {
+                        used_names.insert(snake.to_string(), (normal, snake));
+                        Some(storage)
+                    }
+                }
+                None => None
+            };
+            let place = if jump_table {
+                let index = syn::Index::from(value_count);
+                quote! { values[#index] }
+            } else {
+                quote! { #snake }
+            };
+            if let Some(Storage::Value) = storage {
+                value_count += 1;
+            }
+            fields.push(Field { normal, snake, variant, storage, prefill: prefill.as_ref(), place });
+        }
+
+        // merged enums are stored in their own variants structs
+        let mut merged = vec![];
+        for (path, merged_struct) in &attrs.merge_with {
+            let merged_ident = &path.segments.last().unwrap().ident;
+            let snake = format_ident!("{}", merged_ident.to_string().to_snake_case().into_safe(), span = merged_ident.span());
+            match used_names.get(&snake.to_string()) {
+                Some((normal, _)) => emit_error!(
+                    path, "the field `{}` for merging `{}` is already generated by variant `{}`", snake, merged_ident, normal
+                ),
+                None => merged.push(Merged { path, merged_struct, snake })
+            }
+        }
+
+        // variants that aren't known to the struct (like new variants of a non_exhaustive enum) go into the catch-all map
+        if let Some(catch_all) = catch_all {
+            if let Some((normal, _)) = used_names.get(&catch_all.to_string()) {
+                emit_error!(catch_all, "the catch-all field `{}` is already generated by variant `{}`", catch_all, normal);
+            }
+        }
+
+        let mut value_idents = vec![];
+        let mut value_places = vec![];
+        let mut value_variants = vec![];
+        let mut map_idents = vec![];
+        let mut dense_idents = vec![];
+        let mut multi_idents = vec![];
+        let mut clone_bounds = vec![];
+        let mut to_vec_bounds = vec![];
+        for field in &fields {
+            match field.storage {
+                Some(Storage::Value) => {
+                    value_idents.push(field.snake);
+                    value_places.push(field.place.clone());
+                    value_variants.push(field.variant);
+                }
+                Some(Storage::Map(ty)) => {
+                    map_idents.push(field.snake);
+                    clone_bounds.push(quote! { for<'k> #ty: Clone });
+                }
+                Some(Storage::Dense(..)) => dense_idents.push(field.snake),
+                Some(Storage::Multi(ty)) => {
+                    multi_idents.push(field.snake);
+                    clone_bounds.push(quote! { for<'k> #ty: Clone });
+                    to_vec_bounds.push(quote! { for<'k> #ty: Clone });
+                }
+                None => {}
+            }
+        }
+        if let Some(catch_all) = catch_all {
+            map_idents.push(catch_all);
+            clone_bounds.push(quote! { for<'k> #enum_path: Clone });
+        }
+
+        // a derived `Default` would leave out the keys of `prefill_keys`, so it's implemented with `new_default()` instead
+        let prefills = fields.iter().filter(|field| field.storage.is_some()).filter_map(|field| field.prefill);
+        let prefilled = prefills.clone().next().is_some();
+        let prefill_bounds = if prefills.clone().any(|prefill| prefill.value.is_none()) {
+            vec![quote! { T: Default }]
+        } else {
+            vec![]
+        };
+        let derives = derives.iter().filter(|derive| !(prefilled && storage::is_default(derive))).collect();
+
+        Context {
+            input,
+            enum_path,
+            visibility: &input.vis,
+            enum_name,
+            is_local: enum_path.get_ident().is_some(),
+            attrs,
+            vars,
+            fields,
+            merged,
+            derives,
+            jump_table,
+            boxed_fields,
+            prefill_bounds,
+            derived,
+            impl_attrs,
+            case_lint,
+            struct_hidden,
+            helper_hidden,
+            allow_casts,
+            allow_args,
+            deref,
+            serde_skip,
+            value_ty,
+            into_value,
+            map_ty,
+            map_mod,
+            key_bound,
+            map_with_capacity,
+            value_idents,
+            value_places,
+            value_variants,
+            map_idents,
+            dense_idents,
+            multi_idents,
+            clone_bounds,
+            to_vec_bounds
+        }
+    }
+
+    /// The fields that are stored, along with how.
+    fn stored(&self) -> impl Iterator<Item = (&Field<'a>, &'a Storage)> + '_ {
+        self.fields.iter().filter_map(|field| field.storage.map(|storage| (field, storage)))
+    }
+
+    /// Whether the struct has any fields. If it doesn't (like for an empty enum), it still needs to use `T`.
+    fn has_fields(&self) -> bool {
+        self.jump_table || self.stored().next().is_some() || !self.merged.is_empty() || self.attrs.catch_all.is_some()
+    }
+
+    /// Whether any variants are stored by their keys, so that they might be missing.
+    fn has_keys(&self) -> bool {
+        !self.map_idents.is_empty() || !self.multi_idents.is_empty() || !self.dense_idents.is_empty()
+    }
+
+    /// The variant that the getters match on. An empty enum can only be matched by value.
+    fn scrutinee(&self) -> proc_macro2::TokenStream {
+        if self.fields.is_empty() && self.attrs.catch_all.is_none() {
+            quote! { *var }
+        } else {
+            quote! { var }
+        }
+    }
+
+    /// The fields of the merged structs, and their types.
+    fn merged_idents(&self) -> Vec<&Ident> {
+        self.merged.iter().map(|merged| &merged.snake).collect()
+    }
+
+    fn merged_tys(&self) -> Vec<proc_macro2::TokenStream> {
+        self.merged.iter().map(|Merged {merged_struct, ..}| quote! { #merged_struct<T> }).collect()
+    }
+
+    /// The key type of the catch-all field, which is the enum spanned at the attribute.
+    fn catch_all_ty(&self, catch_all: &Ident) -> syn::Type {
+        let mut key_path = self.enum_path.clone();
+        for segment in &mut key_path.segments {
+            segment.ident.set_span(catch_all.span());
+        }
+        syn::parse_quote! { #key_path }
+    }
+
+    /// The type of the iterator that drains a map of `key`s and `value`s.
+    fn map_drain_ty(&self, key: &dyn quote::ToTokens, value: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        if self.attrs.ordered_maps {
+            quote! { std::collections::btree_map::IntoIter<#key, #value> }
+        } else {
+            quote! { std::collections::hash_map::Drain<'a, #key, #value> }
+        }
+    }
+
+    /// Drains the map in `field`.
+    fn map_drain(&self, field: &Ident) -> proc_macro2::TokenStream {
+        if self.attrs.ordered_maps {
+            quote! { std::mem::take(&mut self.#field).into_iter() }
+        } else {
+            quote! { self.#field.drain() }
+        }
+    }
+}
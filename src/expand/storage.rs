@@ -0,0 +1,389 @@
+//! The fields of the struct, and the methods that create, fill, and empty them.
+
+use syn::spanned::Spanned;
+use quote::{quote, quote_spanned};
+
+use super::{Context, EnumAttrs, Merged, Part, Storage, PrefillKeys};
+
+/// Generates a static assertion that the key type of a map-backed field implements `Hash + Eq` (or `Ord`, if the
+/// maps are `ordered`).
+///
+/// `key_of` names the field in the error message. Without this, a bad key type only shows up as unsatisfied
+/// `HashMap` bounds in the generated getters. `#[diagnostic::on_unimplemented]` needs Rust 1.78, which is why that is
+/// the crate's `rust-version`.
+fn key_assertion(key_of: &str, ty: &syn::Type, ordered: bool) -> proc_macro2::TokenStream {
+    let message = syn::LitStr::new(&format!("`{{Self}}` cannot be used as the key of {}", key_of), ty.span());
+    let (label, bound) = if ordered {
+        ("keys are stored in a `BTreeMap`, so they must implement `Ord`", quote! { ::std::cmp::Ord })
+    } else {
+        ("keys are stored in a `HashMap`, so they must implement `Hash` and `Eq`", quote! { ::std::hash::Hash + ::std::cmp::Eq })
+    };
+    quote_spanned! { ty.span() =>
+        const _: fn() = {
+            #[diagnostic::on_unimplemented(
+                message = #message,
+                label = #label
+            )]
+            trait VariantKey {}
+            impl<K: #bound + ?Sized> VariantKey for K {}
+            fn assert_variant_key<K: VariantKey + ?Sized>() {}
+            assert_variant_key::<#ty>
+        };
+    }
+}
+
+
+/// Generates the key assertions of the fields with keys, which go before everything else so that their errors come first.
+pub(super) fn key_assertions(cx: &Context) -> proc_macro2::TokenStream {
+    let enum_ident = &cx.input.ident;
+    let ordered_maps = cx.attrs.ordered_maps;
+    let mut key_assertions: Vec<_> = cx.stored().filter_map(|(field, storage)| match storage {
+        Storage::Map(ty) | Storage::Multi(ty) => Some(key_assertion(&format!("`{}::{}`", enum_ident, field.normal), ty, ordered_maps)),
+        _ => None
+    }).collect();
+    if let Some(catch_all) = &cx.attrs.catch_all {
+        let enum_ty = cx.catch_all_ty(catch_all);
+        key_assertions.push(key_assertion(&format!("the catch-all field `{}`", catch_all), &enum_ty, ordered_maps));
+    }
+    quote! { #(#key_assertions)* }
+}
+
+/// Whether a derive is `Default`, which `prefill_keys` has to implement itself.
+pub(super) fn is_default(derive: &syn::Path) -> bool {
+    derive.segments.last().is_some_and(|segment| segment.ident == "Default")
+}
+
+/// Generates the struct, and its constructors and the methods that go through all of its fields.
+pub(super) fn generate(cx: &Context) -> Part {
+    Part { methods: methods(cx), items: struct_item(cx) }
+}
+
+/// The arguments of `new()`: a value for each unit variant, and a struct for each merged enum.
+pub(super) fn new_args(cx: &Context) -> Vec<proc_macro2::TokenStream> {
+    let value_ty = &cx.value_ty;
+    let mut new_args: Vec<_> = cx.value_idents.iter().map(|snake| quote! {#snake: #value_ty}).collect();
+    new_args.extend(cx.merged.iter().map(|Merged {snake, merged_struct, ..}| quote! { #snake: #merged_struct<T> }));
+    new_args
+}
+
+/// Constructs the struct from the variables named after the unit fields and merged structs, without filling in the keys
+/// of `prefill_keys`. With `with_capacity`, the hashmaps get room for `map_capacity` values.
+pub(super) fn construct(cx: &Context, with_capacity: bool) -> proc_macro2::TokenStream {
+    let Context { map_ty, value_idents, .. } = cx;
+    let struct_ident = &cx.attrs.struct_ident;
+    // if there are no fields (like for an empty enum), the struct still needs to use `T`
+    if !cx.has_fields() {
+        return quote! { #struct_ident(std::marker::PhantomData) };
+    }
+    let new_map = if with_capacity { cx.map_with_capacity.clone() } else { quote! { #map_ty::new() } };
+    let mut fields: Vec<_> = cx.stored().filter_map(|(field, storage)| {
+        let snake = field.snake;
+        match storage {
+            Storage::Value if cx.jump_table => None,
+            Storage::Value if cx.boxed_fields => Some(quote! {#snake: Box::new(#snake)}),
+            Storage::Value => Some(quote! {#snake}),
+            Storage::Map(_) | Storage::Multi(_) => Some(quote! {#snake: #new_map}),
+            Storage::Dense(_, keys, array) => {
+                let empty_slots = keys.empty_slots(*array);
+                Some(quote! {#snake: #empty_slots})
+            }
+        }
+    }).collect();
+    if cx.jump_table {
+        fields.push(quote! { values: [#(#value_idents),*] });
+    }
+    fields.extend(cx.merged_idents().into_iter().map(|snake| quote! { #snake }));
+    if let Some(catch_all) = &cx.attrs.catch_all {
+        fields.push(quote! { #catch_all: #new_map });
+    }
+    quote! { #struct_ident { #(#fields),* } }
+}
+
+/// Generates the struct, along with the `Default` impl that `prefill_keys` needs.
+fn struct_item(cx: &Context) -> proc_macro2::TokenStream {
+    let Context { visibility, enum_path, derives, case_lint, struct_hidden, serde_skip, map_ty, derived, impl_attrs, .. } = cx;
+    let EnumAttrs { struct_ident, bounds, .. } = cx.attrs;
+    let mut struct_fields: Vec<_> = cx.stored().filter_map(|(field, storage)| {
+        let snake = field.snake;
+        match storage {
+            // the values of a jump table are all in one array
+            Storage::Value if cx.jump_table => None,
+            Storage::Value if cx.boxed_fields => Some(quote! { pub #snake: Box<T> }),
+            Storage::Value => Some(quote! { pub #snake: T }),
+            Storage::Map(ty) => Some(quote! {
+                #serde_skip
+                pub #snake: #map_ty<#ty, T>
+            }),
+            Storage::Dense(_, keys, array) => {
+                let slots_ty = keys.slots_ty(*array);
+                Some(quote! {
+                    pub #snake: #slots_ty
+                })
+            }
+            Storage::Multi(ty) => Some(quote! {
+                #serde_skip
+                pub #snake: #map_ty<#ty, Vec<T>>
+            })
+        }
+    }).collect();
+    if cx.jump_table {
+        let value_count = cx.value_idents.len();
+        struct_fields.push(quote! { pub values: [T; #value_count] });
+    }
+    struct_fields.extend(cx.merged.iter().map(|Merged {snake, merged_struct, ..}| quote! { pub #snake: #merged_struct<T> }));
+    if let Some(catch_all) = &cx.attrs.catch_all {
+        struct_fields.push(quote! { #serde_skip pub #catch_all: #map_ty<#enum_path, T> });
+    }
+    let struct_body = if cx.has_fields() {
+        quote! { { #(#struct_fields),* } }
+    } else {
+        quote! { (std::marker::PhantomData<T>); }
+    };
+
+    // a derived `Default` would leave the keys out, so it's implemented with `new_default()` instead
+    let prefilled = cx.stored().any(|(field, _)| field.prefill.is_some());
+    let prefill_default_impl = if prefilled && cx.attrs.derives.iter().any(is_default) {
+        quote! {
+            #derived
+            #impl_attrs
+            impl<T: #(#bounds)+*> Default for #struct_ident<T> where T: Default {
+                fn default() -> Self {
+                    #struct_ident::new_default()
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let has_keys = cx.has_keys();
+    let enum_name = &cx.enum_name;
+    let struct_doc = if has_keys {
+        format!(
+            "Stores a value for each variant of `{}`.\n\nThe values of tuple and struct variants are stored by their fields, \
+            so they might be missing.",
+            enum_name
+        )
+    } else {
+        format!("Stores a value for each variant of `{}`.", enum_name)
+    };
+    quote! {
+        #prefill_default_impl
+
+        #[derive(#(#derives),*)]
+        #[doc = #struct_doc]
+        #case_lint
+        #struct_hidden
+        #visibility struct #struct_ident<T: #(#bounds)+*> #struct_body
+    }
+}
+
+/// Generates the statements that fill in the keys of `prefill_keys`, into the struct in `prefilled`.
+fn prefills(cx: &Context) -> Vec<proc_macro2::TokenStream> {
+    cx.stored().filter_map(|(field, _)| {
+        let PrefillKeys {keys, value} = field.prefill?;
+        let variant = field.variant;
+        let value = match value {
+            Some(value) => quote! { #value },
+            None => quote! { T::default() }
+        };
+        Some(quote! {
+            for key in #keys {
+                let value: T = #value;
+                prefilled.push(#variant, value);
+            }
+        })
+    }).collect()
+}
+
+/// Generates the constructors, and the methods that reserve, clear, and count the values of every field.
+fn methods(cx: &Context) -> proc_macro2::TokenStream {
+    let Context { value_idents, value_places, map_idents, dense_idents, multi_idents, deref, map_mod, prefill_bounds, .. } = cx;
+    let EnumAttrs { struct_ident, into_args, ordered_maps, .. } = cx.attrs;
+    let (into_args, ordered_maps, jump_table, boxed_fields) = (*into_args, *ordered_maps, cx.jump_table, cx.boxed_fields);
+    let merged_idents = cx.merged_idents();
+    let merged_tys = cx.merged_tys();
+    let new_args = new_args(cx);
+
+    // with `prefill_keys`, the constructors fill in the keys after making the struct
+    let prefills = prefills(cx);
+    let (new_body, with_capacity_body) = (construct(cx, false), construct(cx, true));
+    let (new_body, with_capacity_body) = if prefills.is_empty() {
+        (new_body, with_capacity_body)
+    } else {
+        (
+            quote! {
+                let mut prefilled = #new_body;
+                #(#prefills)*
+                prefilled
+            },
+            quote! {
+                let mut prefilled = #with_capacity_body;
+                #(#prefills)*
+                prefilled
+            }
+        )
+    };
+
+    // hashmaps, dense storage, and boxes can't be created in a const context, but everything else can
+    let convert_values: Vec<_> = if into_args {
+        value_idents.iter().map(|ident| quote! { let #ident = #ident.into(); }).collect()
+    } else {
+        vec![]
+    };
+    // `Into::into` can't be called in a const fn
+    let new_constness = if map_idents.is_empty() && multi_idents.is_empty() && dense_idents.is_empty() && !into_args && !boxed_fields {
+        quote! { const }
+    } else {
+        quote! {}
+    };
+
+    // without any hashmaps, `reserve` wouldn't use its argument. `BTreeMap`s don't have a capacity.
+    let all_map_idents: Vec<_> = map_idents.iter().chain(multi_idents.iter()).collect();
+    let capacity_map_idents = if ordered_maps { vec![] } else { all_map_idents.clone() };
+    let reserve_body = if capacity_map_idents.is_empty() && merged_idents.is_empty() {
+        quote! { let _ = additional; }
+    } else {
+        quote! {
+            #(self.#capacity_map_idents.reserve(additional);)*
+            #(self.#merged_idents.reserve(additional);)*
+        }
+    };
+
+    // unit values are only replaced if `is_missing` says so, and keys are filled in if they aren't stored
+    let apply_defaults_body = if !cx.has_fields() {
+        quote! { let _ = (defaults, is_missing); }
+    } else {
+        let apply_values = if jump_table {
+            quote! {
+                for (value, default) in self.values.iter_mut().zip(IntoIterator::into_iter(defaults.values)) {
+                    if is_missing(value) {
+                        *value = default;
+                    }
+                }
+            }
+        } else {
+            quote! {
+                #(if is_missing(&#deref self.#value_places) {
+                    self.#value_places = defaults.#value_places;
+                })*
+            }
+        };
+        // `multi` fields don't look at the values, so a struct of only them wouldn't use `is_missing`
+        let ignore_missing = if value_places.is_empty() && map_idents.is_empty() && dense_idents.is_empty() && merged_idents.is_empty() {
+            quote! { let _ = is_missing; }
+        } else {
+            quote! {}
+        };
+        quote! {
+            #ignore_missing
+            #apply_values
+            #(for (key, default) in defaults.#map_idents {
+                match self.#map_idents.entry(key) {
+                    #map_mod::Entry::Occupied(mut entry) => if is_missing(entry.get()) {
+                        entry.insert(default);
+                    }
+                    #map_mod::Entry::Vacant(entry) => {
+                        entry.insert(default);
+                    }
+                }
+            })*
+            #(for (slot, default) in self.#dense_idents.iter_mut().zip(IntoIterator::into_iter(defaults.#dense_idents)) {
+                if default.is_some() && slot.as_ref().map_or(true, &mut is_missing) {
+                    *slot = default;
+                }
+            })*
+            #(for (key, default_values) in defaults.#multi_idents {
+                let values = self.#multi_idents.entry(key).or_default();
+                if values.is_empty() {
+                    *values = default_values;
+                }
+            })*
+            #(self.#merged_idents.apply_defaults_where(defaults.#merged_idents, &mut is_missing);)*
+        }
+    };
+
+    // unit fields always hold a value, so they only count towards the length
+    let value_count = value_idents.len();
+    let mut empty_checks: Vec<_> = map_idents.iter().map(|ident| quote! { self.#ident.is_empty() }).collect();
+    empty_checks.extend(dense_idents.iter().map(|ident| quote! { self.#ident.iter().all(Option::is_none) }));
+    empty_checks.extend(multi_idents.iter().map(|ident| quote! { self.#ident.values().all(Vec::is_empty) }));
+    let is_empty_body = if empty_checks.is_empty() { quote! { true } } else { quote! { #(#empty_checks)&&* } };
+
+    quote! {
+        /// Creates the struct from the values of the unit variants, without any values for the other variants.
+        #[must_use]
+        pub #new_constness fn new(#(#new_args),*) -> #struct_ident<T> where #(#prefill_bounds),* {
+            #(#convert_values)*
+            #new_body
+        }
+
+        /// Creates the struct with `T::default()` for each of the unit variants, without any values for the other
+        /// variants.
+        #[must_use]
+        pub fn new_default() -> #struct_ident<T> where T: Default {
+            #(let #value_idents = T::default();)*
+            #(let #merged_idents = <#merged_tys>::new_default();)*
+            #struct_ident::new(#(#value_idents,)* #(#merged_idents),*)
+        }
+
+        /// Creates the struct like `new()`, with room for `map_capacity` values of each of the other variants.
+        #[must_use]
+        pub fn with_capacity(#(#new_args,)* map_capacity: usize) -> #struct_ident<T> where #(#prefill_bounds),* {
+            #(#convert_values)*
+            #with_capacity_body
+        }
+
+        /// Reserves room for `additional` more values of each variant with a key.
+        pub fn reserve(&mut self, additional: usize) {
+            #reserve_body
+        }
+
+        /// Shrinks the storage of the variants with keys as much as possible.
+        pub fn shrink_to_fit(&mut self) {
+            #(self.#capacity_map_idents.shrink_to_fit();)*
+            #(self.#merged_idents.shrink_to_fit();)*
+        }
+
+        /// Removes the values of all of the variants with keys.
+        pub fn clear(&mut self) {
+            #(self.#all_map_idents.clear();)*
+            #(self.#dense_idents.iter_mut().for_each(|value| *value = None);)*
+            #(self.#merged_idents.clear();)*
+        }
+
+        /// Removes the values of all of the variants with keys, and resets the unit variants to `T::default()`.
+        pub fn reset(&mut self) where T: Default {
+            self.clear();
+            #(#deref self.#value_places = T::default();)*
+            #(self.#merged_idents.reset();)*
+        }
+
+        /// Fills in the keys that aren't stored with their values from `defaults`. The values that are already
+        /// stored (including all of the unit variants) are kept.
+        pub fn apply_defaults(&mut self, defaults: Self) {
+            self.apply_defaults_where(defaults, |_| false);
+        }
+
+        /// Like `apply_defaults()`, but also replaces the values for which `is_missing` returns `true`, including
+        /// those of unit variants. A `multi` key is only filled in if it has no values.
+        pub fn apply_defaults_where(&mut self, defaults: Self, mut is_missing: impl FnMut(&T) -> bool) {
+            #apply_defaults_body
+        }
+
+        /// Returns the number of values, including one for each unit variant.
+        #[must_use]
+        pub fn len(&self) -> usize {
+            #value_count
+                #(+ self.#map_idents.len())*
+                #(+ self.#dense_idents.iter().filter(|value| value.is_some()).count())*
+                #(+ self.#multi_idents.values().map(Vec::len).sum::<usize>())*
+        }
+
+        /// Returns `true` if there aren't any values.
+        #[must_use]
+        pub fn is_empty(&self) -> bool {
+            #is_empty_body
+        }
+    }
+}
@@ -0,0 +1,341 @@
+//! The methods that go through every field of the struct at once: `map_ref()`, `transpose()`, `validate_all()`, and
+//! `heap_usage_with()`.
+
+use syn::{Ident, ext::IdentExt};
+use quote::quote;
+
+use super::{Context, EnumAttrs, Field, Merged, Part, Storage};
+
+/// Generates the initializer of a field of the struct returned by `transpose()`, which returns early if one of the
+/// field's values is missing.
+///
+/// `ok` wraps a present value (`Some` or `Ok`), and `collected` is what iterators of wrapped values are collected into.
+fn transpose_field(
+    snake: &Ident,
+    storage: &Storage,
+    ok: &proc_macro2::TokenStream,
+    collected: &proc_macro2::TokenStream
+) -> proc_macro2::TokenStream {
+    let value = match storage {
+        Storage::Value => quote! { self.#snake },
+        Storage::Map(_) => quote! {
+            self.#snake.into_iter().map(|(key, value)| value.map(|value| (key, value))).collect::<#collected>()
+        },
+        Storage::Dense(_, keys, true) => {
+            let len = keys.len();
+            quote! {
+                {
+                    let mut slots: [Option<_>; #len] = std::array::from_fn(|_| None);
+                    for (slot, value) in slots.iter_mut().zip(IntoIterator::into_iter(self.#snake)) {
+                        *slot = match value {
+                            Some(value) => Some(value?),
+                            None => None
+                        };
+                    }
+                    #ok(slots)
+                }
+            }
+        }
+        Storage::Dense(..) => quote! {
+            self.#snake.into_iter().map(|slot| match slot {
+                Some(value) => value.map(Some),
+                None => #ok(None)
+            }).collect::<#collected>()
+        },
+        Storage::Multi(_) => quote! {
+            self.#snake.into_iter().map(
+                |(key, values)| values.into_iter().collect::<#collected>().map(|values| (key, values))
+            ).collect::<#collected>()
+        }
+    };
+    quote! { #snake: #value? }
+}
+
+/// Generates the initializer of a field of the struct returned by `map_ref()`, which calls `f` on every value.
+fn map_ref_field(snake: &Ident, storage: &Storage) -> proc_macro2::TokenStream {
+    let value = match storage {
+        Storage::Value => quote! { f(&self.#snake) },
+        Storage::Map(_) => quote! {
+            self.#snake.iter().map(|(key, value)| (key.clone(), f(value))).collect()
+        },
+        Storage::Dense(_, _, true) => quote! {
+            {
+                let slots = &self.#snake;
+                std::array::from_fn(|index| slots[index].as_ref().map(&mut f))
+            }
+        },
+        Storage::Dense(..) => quote! {
+            self.#snake.iter().map(|slot| slot.as_ref().map(&mut f)).collect()
+        },
+        Storage::Multi(_) => quote! {
+            self.#snake.iter().map(|(key, values)| (key.clone(), values.iter().map(&mut f).collect())).collect()
+        }
+    };
+    quote! { #snake: #value }
+}
+
+/// Generates the statement of `validate_all()` that checks each value of a field, collecting the errors under `name`.
+///
+/// `place` is where the value of a unit variant is, and `variant` builds the variant from `key`.
+fn validate_check(
+    name: &str,
+    place: &proc_macro2::TokenStream,
+    variant: &proc_macro2::TokenStream,
+    storage: &Storage
+) -> proc_macro2::TokenStream {
+    let check = quote! {
+        if let Err(error) = f(&#variant, value) {
+            errors.push((#name.to_string(), error));
+        }
+    };
+    match storage {
+        // typed, so that a boxed value is dereferenced
+        Storage::Value => quote! {
+            let value: &T = &self.#place;
+            #check
+        },
+        Storage::Map(_) => quote! {
+            for (key, value) in &self.#place {
+                let key = key.clone();
+                #check
+            }
+        },
+        Storage::Dense(ty, keys, _) => {
+            let key_at = keys.key(ty);
+            quote! {
+                for (index, value) in self.#place.iter().enumerate() {
+                    if let Some(value) = value {
+                        let key = #key_at;
+                        #check
+                    }
+                }
+            }
+        }
+        Storage::Multi(_) => quote! {
+            for (key, values) in &self.#place {
+                for value in values {
+                    let key = key.clone();
+                    #check
+                }
+            }
+        }
+    }
+}
+
+/// Generates the estimate of the heap memory held by a field, for `heap_usage_with()`. `value_heap` is called with
+/// each value, for the memory that the values hold themselves.
+///
+/// Hashmaps are counted by their capacity (`BTreeMap`s, which have none, by their length), and `boxed` unit values
+/// count their box.
+fn heap_usage_term(place: &proc_macro2::TokenStream, storage: &Storage, boxed: bool, ordered: bool) -> proc_macro2::TokenStream {
+    let size_of = quote! { std::mem::size_of };
+    let slots = |entry: proc_macro2::TokenStream| if ordered {
+        quote! { self.#place.len() * #size_of::<#entry>() }
+    } else {
+        quote! { self.#place.capacity() * #size_of::<#entry>() }
+    };
+    match storage {
+        Storage::Value if boxed => quote! { #size_of::<T>() + value_heap(&*self.#place) },
+        Storage::Value => quote! { value_heap(&self.#place) },
+        Storage::Map(ty) => {
+            let slots = slots(quote! { (#ty, T) });
+            quote! { #slots + self.#place.values().map(&mut value_heap).sum::<usize>() }
+        }
+        Storage::Dense(_, _, array) => {
+            let slots = if *array {
+                quote! { 0 }
+            } else {
+                quote! { self.#place.capacity() * #size_of::<Option<T>>() }
+            };
+            quote! { #slots + self.#place.iter().flatten().map(&mut value_heap).sum::<usize>() }
+        }
+        Storage::Multi(ty) => {
+            let slots = slots(quote! { (#ty, Vec<T>) });
+            quote! {
+                #slots + self.#place.values().map(|values| {
+                    values.capacity() * #size_of::<T>() + values.iter().map(&mut value_heap).sum::<usize>()
+                }).sum::<usize>()
+            }
+        }
+    }
+}
+
+/// Generates the methods that rebuild, check, or measure the struct field by field, and the `transpose()` impls.
+pub(super) fn generate(cx: &Context) -> Part {
+    let Context { enum_path, value_idents, value_places, case_lint, impl_attrs, clone_bounds, .. } = cx;
+    let EnumAttrs { struct_ident, bounds, catch_all, ordered_maps, .. } = cx.attrs;
+    let (ordered_maps, jump_table, boxed_fields) = (*ordered_maps, cx.jump_table, cx.boxed_fields);
+
+    // `transpose()` is generated for both `Option` and `Result` values
+    let mut map_ref_fields = vec![];
+    let mut transpose_option_fields = vec![];
+    let mut transpose_result_fields = vec![];
+    let (option_ok, option_collected) = (quote! { Some }, quote! { Option<_> });
+    let (result_ok, result_collected) = (quote! { Ok }, quote! { Result<_, E> });
+    // `validate_all` checks every value of every field, which needs the keys to be `Clone` like the iterators
+    let mut validate_checks = vec![];
+    // `heap_usage_with` adds up an estimate for each field
+    let mut heap_usage_terms = vec![];
+    for (Field { snake, variant, place, .. }, storage) in cx.stored() {
+        if boxed_fields && matches!(storage, Storage::Value) {
+            map_ref_fields.push(quote! { #snake: Box::new(f(&*self.#snake)) });
+            transpose_option_fields.push(quote! { #snake: Box::new((*self.#snake)?) });
+            transpose_result_fields.push(quote! { #snake: Box::new((*self.#snake)?) });
+        } else if !jump_table {
+            map_ref_fields.push(map_ref_field(snake, storage));
+            transpose_option_fields.push(transpose_field(snake, storage, &option_ok, &option_collected));
+            transpose_result_fields.push(transpose_field(snake, storage, &result_ok, &result_collected));
+        }
+        validate_checks.push(validate_check(&snake.unraw().to_string(), place, variant, storage));
+        heap_usage_terms.push(heap_usage_term(place, storage, boxed_fields, ordered_maps));
+    }
+    if jump_table {
+        map_ref_fields.push(quote! { values: [#(f(&self.#value_places)),*] });
+        let destructure = quote! { let [#(#value_idents),*] = self.values; };
+        transpose_option_fields.push(quote! { values: { #destructure [#(#value_idents?),*] } });
+        transpose_result_fields.push(quote! { values: { #destructure [#(#value_idents?),*] } });
+    }
+    for Merged { snake, .. } in &cx.merged {
+        map_ref_fields.push(quote! { #snake: self.#snake.map_ref(&mut f) });
+        transpose_option_fields.push(quote! { #snake: self.#snake.transpose()? });
+        transpose_result_fields.push(quote! { #snake: self.#snake.transpose()? });
+        heap_usage_terms.push(quote! { self.#snake.heap_usage_with(&mut value_heap) });
+    }
+    if let Some(catch_all) = catch_all {
+        let storage = Storage::Map(cx.catch_all_ty(catch_all));
+        let catch_all_name = catch_all.unraw().to_string();
+        validate_checks.push(quote! {
+            for (var, value) in &self.#catch_all {
+                if let Err(error) = f(var, value) {
+                    errors.push((#catch_all_name.to_string(), error));
+                }
+            }
+        });
+        heap_usage_terms.push(heap_usage_term(&quote! { #catch_all }, &storage, false, ordered_maps));
+        map_ref_fields.push(map_ref_field(catch_all, &storage));
+        transpose_option_fields.push(transpose_field(catch_all, &storage, &option_ok, &option_collected));
+        transpose_result_fields.push(transpose_field(catch_all, &storage, &result_ok, &result_collected));
+    }
+
+    // if there are no fields (like for an empty enum), the struct still needs to use `T`
+    let (map_ref_body, transpose_option_body, transpose_result_body) = if cx.has_fields() {
+        (
+            quote! {
+                let mut f = f;
+                #struct_ident { #(#map_ref_fields),* }
+            },
+            quote! { #struct_ident { #(#transpose_option_fields),* } },
+            quote! { #struct_ident { #(#transpose_result_fields),* } }
+        )
+    } else {
+        (
+            quote! {
+                let _ = f;
+                #struct_ident(std::marker::PhantomData)
+            },
+            quote! { #struct_ident(std::marker::PhantomData) },
+            quote! { #struct_ident(std::marker::PhantomData) }
+        )
+    };
+    let validate_body = if validate_checks.is_empty() {
+        quote! {
+            let _ = &mut f;
+            Ok(())
+        }
+    } else {
+        quote! {
+            let mut errors = vec![];
+            #(#validate_checks)*
+            if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(errors)
+            }
+        }
+    };
+    let heap_usage_body = if heap_usage_terms.is_empty() {
+        quote! {
+            let _ = &mut value_heap;
+            0
+        }
+    } else {
+        quote! { #(#heap_usage_terms)+* }
+    };
+
+    let methods = quote! {
+        /// Estimates the heap memory held by the struct, in bytes: the storage of the variants with keys (and the
+        /// boxes of unit values), but not anything that the values hold themselves.
+        #[must_use]
+        pub fn heap_usage(&self) -> usize {
+            self.heap_usage_with(|_| 0)
+        }
+
+        /// Like `heap_usage()`, but also adds the heap memory that `value_heap` returns for each value.
+        #[must_use]
+        pub fn heap_usage_with(&self, mut value_heap: impl FnMut(&T) -> usize) -> usize {
+            #heap_usage_body
+        }
+
+        /// Checks every value with `f`, returning all of the errors along with the names of the fields that they're
+        /// from. The values of merged structs aren't checked, since they belong to other enums.
+        pub fn validate_all<E>(&self, mut f: impl FnMut(&#enum_path, &T) -> Result<(), E>) -> Result<(), Vec<(String, E)>>
+        where #(#clone_bounds),* {
+            #validate_body
+        }
+
+        /// Creates a struct with the results of calling `f` on each value.
+        #[must_use]
+        pub fn map_ref<'a, U: #(#bounds)+*>(&'a self, f: impl FnMut(&'a T) -> U) -> #struct_ident<U> where #(#clone_bounds),* {
+            #map_ref_body
+        }
+
+        /// Creates a struct of references to each value.
+        #[must_use]
+        pub fn each_ref<'a>(&'a self) -> #struct_ident<&'a T> where &'a T: #(#bounds)+*, #(#clone_bounds),* {
+            self.map_ref(|value| value)
+        }
+
+        /// Clones the struct, so that it can be restored later.
+        #[must_use]
+        pub fn snapshot(&self) -> #struct_ident<T> where T: Clone, #(#clone_bounds),* {
+            self.map_ref(T::clone)
+        }
+
+        /// Replaces the struct with a snapshot of it.
+        pub fn restore(&mut self, snapshot: #struct_ident<T>) {
+            *self = snapshot;
+        }
+
+        /// Groups the variants by their values.
+        #[must_use]
+        pub fn invert(&self) -> std::collections::HashMap<&T, Vec<#enum_path>>
+            where T: std::hash::Hash + Eq, #(#clone_bounds),* {
+            let mut inverted: std::collections::HashMap<&T, Vec<#enum_path>> = std::collections::HashMap::new();
+            for (var, value) in self.iter() {
+                inverted.entry(value).or_default().push(var);
+            }
+            inverted
+        }
+    };
+    let items = quote! {
+    #case_lint
+    #impl_attrs
+    impl<T: #(#bounds)+*> #struct_ident<Option<T>> where Option<T>: #(#bounds)+* {
+        /// Converts the struct into `Some` struct of the values, if all of them are `Some`.
+        #[must_use]
+        pub fn transpose(self) -> Option<#struct_ident<T>> {
+            Some(#transpose_option_body)
+        }
+    }
+
+    #case_lint
+    #impl_attrs
+    impl<T: #(#bounds)+*, E> #struct_ident<Result<T, E>> where Result<T, E>: #(#bounds)+* {
+        /// Converts the struct into `Ok` struct of the values, or the first error.
+        pub fn transpose(self) -> Result<#struct_ident<T>, E> {
+            Ok(#transpose_result_body)
+        }
+    }
+    };
+    Part { methods, items }
+}
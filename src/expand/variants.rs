@@ -0,0 +1,404 @@
+//! Reading the variants of the enum, and how each of them is stored.
+
+use syn::{Ident, ItemEnum, Fields, spanned::Spanned, parse::{Parse, ParseStream}, ext::IdentExt};
+use quote::{quote, quote_spanned};
+use proc_macro_error::emit_error;
+
+use super::attrs::{EnumAttrs, parse_ident_lit};
+
+/// How the values of a variant are stored in the struct.
+pub(super) enum Storage {
+    /// A single `T`, for unit variants.
+    Value,
+    /// A `HashMap<K, T>`, for variants with one field of type `K`.
+    Map(syn::Type),
+    /// A `Vec<Option<T>>` indexed by the key, for variants with a `dense_key` attribute, or an inline
+    /// `[Option<T>; N]` if the attribute includes `array` or lists its keys (the `bool`).
+    Dense(syn::Type, DenseKeys, bool),
+    /// A `HashMap<K, Vec<T>>`, for variants with a `multi` attribute.
+    Multi(syn::Type)
+}
+
+/// The keys that a `dense_key` variant has room for.
+pub(super) enum DenseKeys {
+    /// Every integer below `max`, which is used as the index directly.
+    Max(syn::LitInt),
+    /// A fixed list of literal keys, which are indexed by their position in the list. The position is found with a
+    /// linear `match`.
+    Static(syn::MetaList)
+}
+
+impl DenseKeys {
+    /// The number of slots.
+    pub(super) fn len(&self) -> proc_macro2::TokenStream {
+        match self {
+            DenseKeys::Max(max) => quote! { #max },
+            DenseKeys::Static(list) => {
+                let len = list.nested.len();
+                quote! { #len }
+            }
+        }
+    }
+
+    /// Turns a key into its index. Static keys that aren't in the list get an index past the end.
+    pub(super) fn index(&self, ty: &syn::Type, key: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        match self {
+            // spanned so that a key that isn't an integer is reported at its type
+            DenseKeys::Max(_) => quote_spanned! { ty.span() => #key as usize },
+            DenseKeys::Static(list) => {
+                let keys = list.nested.iter();
+                let indices = 0..list.nested.len();
+                let len = list.nested.len();
+                quote! {
+                    match #key {
+                        #(#keys => #indices,)*
+                        _ => #len
+                    }
+                }
+            }
+        }
+    }
+
+    /// Turns an `index` back into its key.
+    pub(super) fn key(&self, ty: &syn::Type) -> proc_macro2::TokenStream {
+        match self {
+            DenseKeys::Max(_) => quote! { index as #ty },
+            DenseKeys::Static(list) => {
+                let keys = list.nested.iter();
+                quote! { [#(#keys),*][index] }
+            }
+        }
+    }
+
+    /// The type of the slots: a `Vec`, or an array if `array` is given.
+    pub(super) fn slots_ty(&self, array: bool) -> proc_macro2::TokenStream {
+        let len = self.len();
+        if array {
+            quote! { [Option<T>; #len] }
+        } else {
+            quote! { Vec<Option<T>> }
+        }
+    }
+
+    /// Constructs the slots, all empty.
+    pub(super) fn empty_slots(&self, array: bool) -> proc_macro2::TokenStream {
+        let len = self.len();
+        if array {
+            quote! { std::array::from_fn(|_| None) }
+        } else {
+            quote! { std::iter::repeat_with(|| None).take(#len).collect() }
+        }
+    }
+
+    /// The type of the by-value iterator over the slots.
+    pub(super) fn owned_iter_ty(&self, array: bool) -> proc_macro2::TokenStream {
+        let len = self.len();
+        if array {
+            quote! { std::array::IntoIter<Option<T>, #len> }
+        } else {
+            quote! { std::vec::IntoIter<Option<T>> }
+        }
+    }
+
+    /// Describes the keys that can be stored, to finish "the key isn't ...".
+    pub(super) fn bound(&self) -> String {
+        match self {
+            DenseKeys::Max(max) => format!("below {}", max),
+            DenseKeys::Static(_) => "one of the keys of the variant".to_string()
+        }
+    }
+}
+
+impl quote::ToTokens for DenseKeys {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        match self {
+            DenseKeys::Max(max) => max.to_tokens(tokens),
+            DenseKeys::Static(list) => list.to_tokens(tokens)
+        }
+    }
+}
+
+/// Stores basic information about variants, borrowing from the enum.
+pub(super) struct VariantInfo<'a> {
+    pub(super) normal: &'a Ident,
+    pub(super) snake: Ident,
+    /// Constructs or matches the variant, with its field (if any) given by `key`.
+    ///
+    /// When matched against a reference, `key` is bound by reference.
+    pub(super) variant: proc_macro2::TokenStream,
+    /// `None` if the variant isn't supported.
+    pub(super) storage: Option<Storage>,
+    /// The keys that `new()` fills in, from `prefill_keys`.
+    pub(super) prefill: Option<PrefillKeys>
+}
+
+/// The arguments of a `prefill_keys` attribute.
+pub(super) struct PrefillKeys {
+    /// An iterator over the keys, which are either listed or given by `keys = ...`.
+    pub(super) keys: proc_macro2::TokenStream,
+    /// The value given by `value = ...`, or `None` for `T::default()`.
+    pub(super) value: Option<syn::Expr>
+}
+
+impl Parse for PrefillKeys {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut listed = vec![];
+        let mut keys = None;
+        let mut value = None;
+        while !input.is_empty() {
+            if input.peek(Ident) && input.peek2(syn::Token![=]) {
+                let name: Ident = input.parse()?;
+                input.parse::<syn::Token![=]>()?;
+                let slot = if name == "keys" {
+                    &mut keys
+                } else if name == "value" {
+                    &mut value
+                } else {
+                    return Err(syn::Error::new(name.span(), "expected `keys = ...` or `value = ...`"));
+                };
+                if slot.is_some() {
+                    return Err(syn::Error::new(name.span(), format!("`{}` is given more than once", name)));
+                }
+                *slot = Some(input.parse::<syn::Expr>()?);
+            } else {
+                listed.push(input.parse::<syn::Expr>()?);
+            }
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<syn::Token![,]>()?;
+        }
+        let keys = match (keys, listed.first()) {
+            (None, Some(_)) => quote! { IntoIterator::into_iter([#(#listed),*]) },
+            (Some(keys), None) => quote! { IntoIterator::into_iter(#keys) },
+            (Some(_), Some(first)) => return Err(syn::Error::new(
+                first.span(), "the keys can be listed or given with `keys = ...`, but not both"
+            )),
+            (None, None) => return Err(input.error("expected keys, like `prefill_keys(\"en\", \"fr\")` or `prefill_keys(keys = ...)`"))
+        };
+        Ok(PrefillKeys { keys, value })
+    }
+}
+
+/// Reads the variants of the enum, in the order of the fields.
+pub(super) fn read<'a>(input: &'a ItemEnum, enum_path: &syn::Path, attrs: &EnumAttrs) -> Vec<VariantInfo<'a>> {
+    let EnumAttrs { rename_fields, preserve_case, alphabetical, by_discriminant, field_order, .. } = attrs;
+    let enum_ident = &input.ident;
+    let mut vars: Vec<_> = input.variants.iter().map(
+        |var| {
+            // read the `field_name`, `dense_key`, `multi`, and `prefill_keys` attributes, falling back to the defaults if
+            // they are absent or invalid.
+            let mut field_name = None;
+            let mut dense_max = None;
+            let mut dense_array = None;
+            let mut multi = None;
+            let mut prefill = None;
+            for attr in &var.attrs {
+                if attr.path.is_ident("field_name") {
+                    match attr.parse_meta() {
+                        Ok(syn::Meta::NameValue(syn::MetaNameValue {lit, ..})) => {
+                            if field_name.is_some() {
+                                emit_error!(attr, "`field_name` can only be given once per variant");
+                            } else {
+                                field_name = Some(parse_ident_lit(&lit, true));
+                            }
+                        }
+                        Ok(syn::Meta::List(_)) => match attr.parse_args::<Ident>() {
+                            Ok(ident) if field_name.is_some() => emit_error!(ident, "`field_name` can only be given once per variant"),
+                            Ok(ident) => field_name = Some(Some(ident)),
+                            Err(e) => emit_error!(e.span(), "{}", e)
+                        }
+                        Ok(meta) => emit_error!(meta, "expected a name, like `field_name = \"...\"` or `field_name(...)`"),
+                        Err(e) => emit_error!(e.span(), "{}", e)
+                    }
+                } else if attr.path.is_ident("dense_key") {
+                    match attr.parse_meta() {
+                        Ok(syn::Meta::List(syn::MetaList {nested, ..})) => {
+                            for meta in nested {
+                                let keys = match meta {
+                                    syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("array") => {
+                                        if dense_array.is_some() {
+                                            emit_error!(path, "`array` is given more than once");
+                                        }
+                                        dense_array = Some(path);
+                                        continue;
+                                    }
+                                    syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {path, lit: syn::Lit::Int(max), ..}))
+                                        if path.is_ident("max") => DenseKeys::Max(max),
+                                    syn::NestedMeta::Meta(syn::Meta::List(list)) if list.path.is_ident("keys") => {
+                                        let mut seen = std::collections::HashSet::new();
+                                        for key in &list.nested {
+                                            match key {
+                                                syn::NestedMeta::Lit(syn::Lit::Str(_) | syn::Lit::Int(_)) => {
+                                                    if !seen.insert(quote!(#key).to_string()) {
+                                                        emit_error!(key, "the key is given more than once");
+                                                    }
+                                                }
+                                                _ => emit_error!(key, "expected a string or integer literal")
+                                            }
+                                        }
+                                        if list.nested.is_empty() {
+                                            emit_error!(list, "expected at least one key, like `keys(\"if\", \"else\")`");
+                                        }
+                                        DenseKeys::Static(list)
+                                    }
+                                    _ => {
+                                        emit_error!(meta, "expected an integer bound or a list of keys, like `max = 64` or `keys(\"if\", \"else\")`");
+                                        continue;
+                                    }
+                                };
+                                if dense_max.is_some() {
+                                    emit_error!(keys, "`dense_key` takes only one of `max` and `keys`");
+                                } else {
+                                    dense_max = Some(keys);
+                                }
+                            }
+                        }
+                        Ok(meta) => emit_error!(meta, "expected a bound, like `dense_key(max = 64)` or `dense_key(keys(...))`"),
+                        Err(e) => emit_error!(e.span(), "{}", e)
+                    }
+                } else if attr.path.is_ident("multi") {
+                    match attr.parse_meta() {
+                        Ok(syn::Meta::Path(path)) => multi = Some(path),
+                        Ok(meta) => emit_error!(meta, "`multi` doesn't take any arguments"),
+                        Err(e) => emit_error!(e.span(), "{}", e)
+                    }
+                } else if attr.path.is_ident("prefill_keys") {
+                    match attr.parse_args::<PrefillKeys>() {
+                        Ok(_) if prefill.is_some() => emit_error!(attr, "`prefill_keys` can only be given once per variant"),
+                        Ok(keys) => prefill = Some((attr, keys)),
+                        Err(e) => emit_error!(e.span(), "{}", e)
+                    }
+                }
+            }
+            let snake = match field_name {
+                Some(Some(ident)) => ident,
+                _ => rename_fields.rename(&var.ident, preserve_case.is_some())
+            };
+
+            let normal = &var.ident;
+            // variants without a payload (like `Ping(())` or `Marker {}`) are stored like unit variants
+            let is_unit_ty = |ty: &syn::Type| matches!(ty, syn::Type::Tuple(tuple) if tuple.elems.is_empty());
+            let (variant, key_ty, is_unit) = match &var.fields {
+                Fields::Unit => (quote! { #enum_path::#normal }, None, true),
+                Fields::Unnamed(syn::FieldsUnnamed { unnamed, .. }) if unnamed.is_empty() => {
+                    (quote! { #enum_path::#normal() }, None, true)
+                }
+                Fields::Named(syn::FieldsNamed { named, .. }) if named.is_empty() => {
+                    (quote! { #enum_path::#normal {} }, None, true)
+                }
+                Fields::Unnamed(syn::FieldsUnnamed { unnamed, .. }) if unnamed.len() == 1 && is_unit_ty(&unnamed[0].ty) => {
+                    (quote! { #enum_path::#normal(()) }, None, true)
+                }
+                Fields::Named(syn::FieldsNamed { named, .. }) if named.len() == 1 && is_unit_ty(&named[0].ty) => {
+                    let ident = named[0].ident.as_ref().unwrap();
+                    (quote! { #enum_path::#normal { #ident: () } }, None, true)
+                }
+                Fields::Unnamed(syn::FieldsUnnamed { unnamed, .. }) if unnamed.len() == 1 => {
+                    let ty = unnamed.first().unwrap().ty.clone();
+                    (quote! { #enum_path::#normal(key) }, Some(ty), false)
+                }
+                Fields::Named(syn::FieldsNamed { named, .. }) if named.len() == 1 => {
+                    let field = named.first().unwrap();
+                    let ident = field.ident.as_ref().unwrap();
+                    (quote! { #enum_path::#normal { #ident: key } }, Some(field.ty.clone()), false)
+                }
+                // if the variant can't be supported, report it and give it unreachable match arms,
+                // so that the rest of the struct is still generated without follow-up errors.
+                Fields::Unnamed(syn::FieldsUnnamed { unnamed, .. }) => {
+                    emit_error!(unnamed[1], "only tuples with one value are allowed");
+                    (quote! { #enum_path::#normal { .. } }, None, false)
+                }
+                Fields::Named(syn::FieldsNamed { named, .. }) => {
+                    emit_error!(named[1], "only structs with one field are allowed");
+                    (quote! { #enum_path::#normal { .. } }, None, false)
+                }
+            };
+            if let (Some(array), None) = (&dense_array, &dense_max) {
+                emit_error!(array, "`array` needs a bound, like `dense_key(max = 64, array)`");
+            }
+            if let (Some(multi), Some(max)) = (&multi, &dense_max) {
+                emit_error!(max, "`dense_key` can't be combined with `multi`");
+                emit_error!(multi, "`multi` is given here");
+            }
+            if is_unit {
+                if let Some((attr, _)) = prefill.take() {
+                    emit_error!(attr, "`prefill_keys` can only be used on variants with a key");
+                }
+            }
+            let storage = match (is_unit, key_ty, dense_max, multi) {
+                (true, _, None, None) => Some(Storage::Value),
+                (_, Some(ty), None, None) => Some(Storage::Map(ty)),
+                (_, Some(ty), Some(max), None) => {
+                    // listed keys always fit in an array, since their number is known
+                    let array = dense_array.is_some() || matches!(max, DenseKeys::Static(_));
+                    Some(Storage::Dense(ty, max, array))
+                }
+                (_, Some(ty), _, Some(_)) => Some(Storage::Multi(ty)),
+                (true, _, max, multi) => {
+                    if let Some(max) = max {
+                        emit_error!(max, "`dense_key` can only be used on variants with a key");
+                    }
+                    if let Some(multi) = multi {
+                        emit_error!(multi, "`multi` can only be used on variants with a key");
+                    }
+                    Some(Storage::Value)
+                }
+                _ => None
+            };
+            VariantInfo {
+                normal,
+                snake,
+                variant,
+                storage,
+                prefill: prefill.map(|(_, keys)| keys)
+            }
+        }
+    ).collect();
+
+    // everything that goes through the fields in order (like `new`, serialization, and iteration) follows the field order
+    if *alphabetical {
+        vars.sort_by_key(|var| var.snake.unraw().to_string());
+    } else if *by_discriminant {
+        // the discriminants have to be known here, so they can only be integer literals (or left out)
+        let mut next = 0i128;
+        let mut discriminants = std::collections::HashMap::new();
+        for var in &input.variants {
+            if let Some((_, expr)) = &var.discriminant {
+                match discriminant_value(expr) {
+                    Some(value) => next = value,
+                    None => emit_error!(expr, "`field_order(discriminant)` needs the discriminants to be integer literals")
+                }
+            }
+            discriminants.insert(var.ident.to_string(), next);
+            next = next.wrapping_add(1);
+        }
+        vars.sort_by_key(|var| discriminants.get(&var.normal.to_string()).copied());
+    } else if let Some(order) = field_order {
+        let mut positions = std::collections::HashMap::new();
+        for (position, ident) in order.iter().enumerate() {
+            if !vars.iter().any(|var| var.normal == ident) {
+                emit_error!(ident, "`{}` isn't a variant of `{}`", ident, enum_ident);
+            } else if positions.insert(ident.to_string(), position).is_some() {
+                emit_error!(ident, "`{}` is given more than once", ident);
+            }
+        }
+        for var in &vars {
+            if !positions.contains_key(&var.normal.to_string()) {
+                emit_error!(var.normal, "the variant `{}` is missing from `field_order`", var.normal);
+            }
+        }
+        vars.sort_by_key(|var| positions.get(&var.normal.to_string()).copied());
+    }
+    vars
+}
+
+/// Reads a discriminant that is an integer literal (like `4` or `-1`), for `field_order(discriminant)`.
+fn discriminant_value(expr: &syn::Expr) -> Option<i128> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {lit: syn::Lit::Int(int), ..}) => int.base10_parse().ok(),
+        syn::Expr::Unary(syn::ExprUnary {op: syn::UnOp::Neg(_), expr, ..}) => discriminant_value(expr).and_then(i128::checked_neg),
+        syn::Expr::Paren(syn::ExprParen {expr, ..}) | syn::Expr::Group(syn::ExprGroup {expr, ..}) => discriminant_value(expr),
+        _ => None
+    }
+}
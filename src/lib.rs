@@ -36,31 +36,33 @@
 //!         }
 //!     }
 //!
+//!     #[track_caller]
 //!     pub fn get_unchecked(&self, var: &Hello) -> &T {
 //!         match var {
-//!             &Hello::World => &self.world,
-//!             &Hello::There => &self.there
+//!             Hello::World => &self.world,
+//!             Hello::There => &self.there
 //!         }
 //!     }
 //!
+//!     #[track_caller]
 //!     pub fn get_mut_unchecked(&mut self, var: &Hello) -> &mut T {
 //!         match var {
-//!             &Hello::World => &mut self.world,
-//!             &Hello::There => &mut self.there
+//!             Hello::World => &mut self.world,
+//!             Hello::There => &mut self.there
 //!         }
 //!     }
 //!
 //!     pub fn get(&self, var: &Hello) -> Option<&T> {
 //!         match var {
-//!             &Hello::World => Some(&self.world),
-//!             &Hello::There => Some(&self.there)
+//!             Hello::World => Some(&self.world),
+//!             Hello::There => Some(&self.there)
 //!         }
 //!     }
 //!
 //!     pub fn get_mut(&mut self, var: &Hello) -> Option<&mut T> {
 //!         match var {
-//!             &Hello::World => Some(&mut self.world),
-//!             &Hello::There => Some(&mut self.there)
+//!             Hello::World => Some(&mut self.world),
+//!             Hello::There => Some(&mut self.there)
 //!         }
 //!     }
 //! }
@@ -82,6 +84,9 @@
 //! assert_eq!(hello.world, *hello.get_unchecked(&Hello::World));
 //! ```
 //!
+//! If `T` implements `Default`, `HelloStruct::new_default()` creates the struct with `T::default()` for every variant,
+//! without having to derive `Default` for the struct.
+//!
 //! The getters can be particularly useful with the [enum-iterator](https://docs.rs/crate/enum-iterator/) crate. For basic enums,
 //! the checked-getters will always return `Some(...)`, so using `get_unchecked` is recommended, *but this is not the case when the enum contains tuple variants*.
 //!
@@ -108,7 +113,8 @@
 //!
 //! will produce a struct with name `SomeOtherName`.
 //!
-//! You can also rename the individual fields manually with the `field_name` attribute. For example, this:
+//! You can also rename the individual fields manually with the `field_name` attribute, which takes the name as a
+//! string or as an identifier (like `#[field_name(this_name)]`). For example, this:
 //!
 //! ```
 //! # use variants_struct::VariantsStruct;
@@ -126,6 +132,84 @@
 //! }
 //! ```
 //!
+//! The `preserve_case` attribute names every field exactly like its variant, for enums whose variant names don't
+//! survive the conversion to snake case (like `HTTPOk`, which would become `httpok`):
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[preserve_case]
+//! enum Status {
+//!     HTTPOk,
+//!     NotFound
+//! }
+//!
+//! let status = StatusStruct::new(200, 404);
+//! assert_eq!(status.HTTPOk, 200);
+//! ```
+//!
+//! To rename all of the fields in some other way, the `rename_fields` attribute takes the case to convert the variant
+//! names to (one of `snake_case`, `SCREAMING_SNAKE_CASE`, `camelCase`, `PascalCase`, `lowercase`, or `UPPERCASE`),
+//! and a prefix and suffix to add to them. Only the fields without a `field_name` are renamed:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[rename_fields(case = "SCREAMING_SNAKE_CASE", prefix = "V_")]
+//! enum Status {
+//!     Ok,
+//!     NotFound,
+//!     #[field_name = "other"] Other
+//! }
+//!
+//! let status = StatusStruct::new(200, 404, 500);
+//! assert_eq!(status.V_NOT_FOUND, 404);
+//! assert_eq!(status.other, 500);
+//! ```
+//!
+//! The case can also be given by itself, like `#[rename_fields = "camelCase"]`.
+//!
+//! A prefix shared by the variants can be removed before the conversion with `strip_prefix`, either by giving the
+//! prefix or by leaving it to be detected from the variant names:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[rename_fields(strip_prefix)]
+//! enum Kind {
+//!     KindFoo,
+//!     KindBar
+//! }
+//!
+//! let kinds = KindStruct::new(1, 2);
+//! assert_eq!(kinds.foo + kinds.bar, 3);
+//! ```
+//!
+//! Variants that don't start with the prefix (or that would be left without a name) keep their whole name.
+//!
+//! ## Field Order
+//!
+//! The fields are generated in the order the variants are declared. `field_order(alphabetical)` sorts them by their
+//! names instead, `field_order(discriminant)` sorts them by their discriminants (which have to be integer literals, or
+//! left out), and `field_order(...)` with a list of every variant puts them in that order. The order is used
+//! everywhere the fields come one after another: the arguments of `new()`, derived serialization, iteration, and the
+//! array of a `jump_table`. Since an explicit list has to name every variant, a new variant can't change a format
+//! that depends on the order until it's given a place.
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[field_order(There, World)]
+//! enum Hello {
+//!     World,
+//!     There
+//! }
+//!
+//! let hello = HelloStruct::new(1, 2);
+//! assert_eq!(hello.there, 1);
+//! assert_eq!(hello.world, 2);
+//! ```
+//!
 //! ## Derives
 //!
 //! By default no derives are applied to the generated struct. You can add derive macro invocations with the `struct_derive` attribute. For example, this:
@@ -155,6 +239,34 @@
 //! // impl block omitted
 //! ```
 //!
+//! To serialize the struct as a sequence of `(variant, value)` pairs instead (using the enum's own serde
+//! representation), use `#[serde_pairs]` instead of deriving `Serialize` and `Deserialize`. The enum has to implement
+//! them too, and every unit variant has to be in the sequence when deserializing:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! use serde::{Serialize, Deserialize};
+//!
+//! #[derive(VariantsStruct, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+//! #[serde_pairs]
+//! enum Hello {
+//!     World,
+//!     There(i32)
+//! }
+//!
+//! let mut hello = HelloStruct::new(1);
+//! hello.there.insert(2, 3);
+//! assert_eq!(serde_json::to_string(&hello).unwrap(), r#"[["World",1],[{"There":2},3]]"#);
+//! ```
+//!
+//! With `#[serde_skip_maps]`, the hashmaps of tuple and struct variants (and of `catch_all`) are left out of
+//! serialization, and are empty after deserializing, so only the fixed fields are on the wire. This needs `Serialize`
+//! or `Deserialize` in `struct_derive`.
+//!
+//! Serializing only borrows the struct, so a table that is shared (like one returned by a cell's `load()`) can be
+//! serialized where it is. For a view that borrows the values, `each_ref()` returns a `HelloStruct<&T>`, which is
+//! serialized the same way as the owned struct without cloning any of the values.
+//!
 //! ## Trait Bounds
 //!
 //! By default the struct's type argument `T` has no trait bounds, but you can add them with the `struct_bounds` attribute. For example, this:
@@ -182,6 +294,34 @@
 //! }
 //! ```
 //!
+//! The bounds can be separated by `,` or `+`, and can be anything that's allowed after `T:`, like `'static` or a
+//! higher-ranked bound such as `for<'a> PartialEq<&'a str>`. They're put on every impl, including the `Index` impls.
+//! `T` can't be `?Sized`, since the values are stored by value, and `'static` is the only lifetime that can be used.
+//!
+//! ## Concrete Value Types
+//!
+//! Some methods only make sense for numbers. Rather than bounding `T` everywhere, the `impl_for` attribute adds an
+//! impl block for one concrete value type, with the extras named in `extras(...)`:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[impl_for(f32, extras(sum, lerp))]
+//! enum Channel {
+//!     Left,
+//!     Right
+//! }
+//!
+//! let quiet = ChannelStruct::new(0.0, 0.5);
+//! let loud = ChannelStruct::new(1.0, 1.0);
+//! assert_eq!(quiet.sum(), 0.5);
+//! assert_eq!(quiet.lerp(&loud, 0.5).right, 0.75);
+//! ```
+//!
+//! The extras are `sum()`, `product()`, `min()`, `max()` (which return `None` for a struct with no values), and
+//! `lerp(other, t)`, which only changes the values that `other` also has. Leaving out `extras` generates all of them,
+//! and the attribute can be given once for each type.
+//!
 //! ## Combinations
 //!
 //! Note that many derives don't require that the type argument `T` fulfills any trait bounds. For example, applying the `Clone`
@@ -201,10 +341,90 @@
 //!
 //! These two attributes, and the `struct_name` attribute, can be used in any order, or even multiple times (although that wouldn't be very readable).
 //!
+//! ## Comparing Different Value Types
+//!
+//! A derived `PartialEq` only compares structs with the same `T`. With the `cross_eq` attribute, a `HelloStruct<T>` can be
+//! compared with a `HelloStruct<U>` whenever `T: PartialEq<U>`, like `&str` and `String`. The unit variants are compared
+//! pairwise and the hashmaps key by key. Since this includes the case where `T` and `U` are the same, `cross_eq` replaces
+//! `PartialEq` in `struct_derive` (an `Eq` derive still works with it).
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[cross_eq]
+//! enum Hello {
+//!     World,
+//!     There(i32)
+//! }
+//!
+//! let mut expected = HelloStruct::new("hi");
+//! expected.there_insert(3, "three");
+//! let mut actual = HelloStruct::new(String::from("hi"));
+//! actual.there_insert(3, String::from("three"));
+//! assert!(expected == actual);
+//! ```
+//!
+//! ## Documentation
+//!
+//! The generated struct, its methods, and its iterators have generated docs. To keep them out of the documentation
+//! of your crate, `#[struct_doc(hidden)]` marks the struct and all of the other generated types as `#[doc(hidden)]`,
+//! and `#[struct_doc(hidden_helpers)]` only hides the other types (like the iterators and the `Handlers` alias):
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[struct_doc(hidden_helpers)]
+//! pub enum Hello {
+//!     World,
+//!     There
+//! }
+//! ```
+//!
+//! The generated items are always next to the enum. To document them somewhere else, re-export them from there with
+//! `#[doc(inline)] pub use`.
+//!
+//! ## Impl Attributes
+//!
+//! The `impl_attr` attribute puts attributes on every generated `impl` block (but not on the struct itself), so the
+//! generated API can be gated behind a feature or have lints adjusted:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[impl_attr(cfg(not(feature = "no_variants_struct")), allow(clippy::must_use_candidate))]
+//! enum Hello {
+//!     World,
+//!     There
+//! }
+//!
+//! let hello = HelloStruct::new(1, 2);
+//! assert_eq!(hello.get(&Hello::There), Some(&2));
+//! ```
+//!
+//! ## Conversions
+//!
+//! With the `into_args` attribute, `new()`, `with_capacity()`, `push()`, and the `insert` accessors take any
+//! `impl Into<T>` instead of `T`, so the values don't need `.into()` at each call. (`new()` can't be a `const fn` then.)
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[into_args]
+//! enum Hello {
+//!     World,
+//!     There(u8)
+//! }
+//!
+//! let mut hello: HelloStruct<String> = HelloStruct::new("hello world");
+//! hello.there_insert(2, "hello there");
+//! assert_eq!(hello.there[&2], "hello there");
+//! ```
+//!
 //! # Tuple and Struct Variants
 //!
-//! Tuple variants are turned into a `HashMap`, where the data stored in the tuple is the key (so the data must implement `Hash`).
-//! Unfortunately, variants with more than one field in them are not supported.
+//! Tuple variants are turned into a `HashMap`, where the data stored in the tuple is the key (so the data must implement `Hash` and `Eq`).
+//! Unfortunately, variants with more than one field in them are not supported. Variants that don't carry any data (like
+//! `Ping(())`, `Ping()`, or `Ping {}`) are stored like unit variants.
 //!
 //! Tuple variants are omitted from the struct's `new` function. For example, this:
 //!
@@ -237,268 +457,1710 @@
 //!         }
 //!     }
 //!
+//!     #[track_caller]
 //!     pub fn get_unchecked(&self, var: &Hello) -> &T {
 //!         match var {
-//!             &Hello::World => &self.world,
-//!             &Hello::There(key) => self.there.get(&key)
+//!             Hello::World => &self.world,
+//!             Hello::There(key) => self.there.get(key)
 //!                 .expect("tuple variant key not found in hashmap")
 //!         }
 //!     }
 //!
+//!     #[track_caller]
 //!     pub fn get_mut_unchecked(&mut self, var: &Hello) -> &mut T {
 //!         match var {
-//!             &Hello::World => &mut self.world,
-//!             &Hello::There(key) => self.there.get_mut(&key)
+//!             Hello::World => &mut self.world,
+//!             Hello::There(key) => self.there.get_mut(key)
 //!                 .expect("tuple variant key not found in hashmap")
 //!         }
 //!     }
 //!
 //!     pub fn get(&self, var: &Hello) -> Option<&T> {
 //!         match var {
-//!             &Hello::World => Some(&self.world),
-//!             &Hello::There(key) => self.there.get(&key)
+//!             Hello::World => Some(&self.world),
+//!             Hello::There(key) => self.there.get(key)
 //!         }
 //!     }
 //!
 //!     pub fn get_mut(&mut self, var: &Hello) -> Option<&mut T> {
 //!         match var {
-//!             &Hello::World => Some(&mut self.world),
-//!             &Hello::There(key) => self.there.get_mut(&key)
+//!             Hello::World => Some(&mut self.world),
+//!             Hello::There(key) => self.there.get_mut(key)
 //!         }
 //!     }
 //! }
 //! ```
 //!
 //! Notice that the `new` function now only takes the `world` argument, and the unchecked getter methods query the hashmap and unwrap the result.
+//! There is also a `with_capacity` function, which takes the same arguments plus a `map_capacity` that every hashmap is created with
+//! (like `HelloStruct::with_capacity(world, 100)`), to avoid rehashing while large tables are built.
+//! Similarly, `reserve(additional)` and `shrink_to_fit()` pass through to every hashmap in the struct. To manage the capacity
+//! of a single variant's hashmap, use its field directly.
+//!
+//! To reuse a struct without reallocating, `clear()` removes every entry from the hashmaps (keeping their capacity), and
+//! `reset()` also sets the other fields back to `T::default()`. To flush the entries instead of dropping them, `drain()`
+//! removes them and yields each value paired with its variant, leaving the unit variants as they are.
+//!
+//! `len()` counts every stored value: one for each unit variant, plus the number of entries in each hashmap. Since the unit
+//! variants always have a value, `is_empty()` only checks that there are no entries in the hashmaps.
+//!
+//! `to_vec()` consumes the struct and returns every value paired with its variant: first the unit variants in the order
+//! they are declared, and then the entries of each hashmap. `into_values()` is the same, but only yields the values.
+//! The unchecked getters are `#[track_caller]`, so a missing key is reported at the line that called them.
 //!
 //! The same can also be done in struct variants that have only one field.
-
-use proc_macro::TokenStream;
-use syn::{Ident, parse_macro_input, ItemEnum, Fields};
-use quote::{quote, format_ident};
-use inflector::Inflector;
-use proc_macro_error::{proc_macro_error, emit_error, abort};
-use check_keyword::CheckKeyword;
-
-/// Stores basic information about variants.
-struct VariantInfo {
-    normal: Ident,
-    snake: Ident,
-    fields: Fields
-}
-
-/// Derives the variants struct and impl.
-#[proc_macro_error]
-#[proc_macro_derive(VariantsStruct, attributes(struct_bounds, struct_derive, struct_name, field_name))]
-pub fn variants_struct(input: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(input as ItemEnum);
-    let enum_ident = input.ident.clone();
-    let mut struct_ident = format_ident!("{}Struct", input.ident);
-    let visibility = input.vis.clone();
-
-    // read the `struct_bounds`, `struct_derive`, and `struct_name` attributes. (ignore any others)
-    let mut bounds = vec![];
-    let mut derives = vec![];
-    for attr in input.clone().attrs {
-        match attr.parse_meta() {
-            Ok(syn::Meta::List(syn::MetaList {path, nested, ..})) => {
-                if let Some(ident) = path.get_ident() {
-                    let attr_name = ident.to_string();
-                    if attr_name == "struct_bounds" || attr_name == "struct_derive" {
-                        let mut paths = vec![];
-                        for meta in nested {
-                            match meta {
-                                syn::NestedMeta::Meta(syn::Meta::Path(path)) => {
-                                    paths.push(path.clone());
-                                }
-                                _ => emit_error!(path, "only path arguments are accepted")
-                            }
-                        }
-                        if attr_name == "struct_bounds" {
-                            bounds.extend(paths);
-                        } else {
-                            derives.extend(paths);
-                        }
-                    }
-                }
-            }
-            Ok(syn::Meta::NameValue(syn::MetaNameValue {path, lit, ..})) => {
-                if let Some(ident) = path.get_ident() {
-                    let attr_name = ident.to_string();
-                    if attr_name == "struct_name" {
-                        if let syn::Lit::Str(lit_str) = lit {
-                            struct_ident = format_ident!("{}", lit_str.value());
-                        } else {
-                            emit_error!(lit, "must be a str literal");
-                        }
-                    }
-                }
-            }
-            _ => {}
-        }
-    }
-
-    if input.variants.len() == 0 {
-        return (quote! {
-            #[derive(#(#derives),*)]
-            #visibility struct #struct_ident;
-        }).into()
-    }
-
-    let vars: Vec<_> = input.clone().variants.iter().map(
-        |var| {
-            let snake = {
-                let names: Vec<_> = var.attrs.iter().filter_map(
-                    |attr| {
-                        match attr.parse_meta() {
-                            Ok(syn::Meta::NameValue(syn::MetaNameValue {path, lit, ..})) => {
-                                if let Some(ident) = path.get_ident() {
-                                    if ident.to_string() == "field_name" {
-                                        if let syn::Lit::Str(lit_str) = lit {
-                                            Some(lit_str.value())
-                                        } else {
-                                            abort!(lit, "must be a string literal");
-                                        }
-                                    } else {
-                                        None
-                                    }
-                                } else {
-                                    None
-                                }
-                            }
-                            _ => None
-                        }
-                    }
-                ).collect();
-                if names.is_empty() {
-                    let name = var.ident.to_string().to_snake_case();
-                    format_ident!("{}", name.into_safe())
-                } else {
-                    format_ident!("{}", names.first().unwrap().to_safe())
-                }
-            };
-            VariantInfo {
-                normal: var.ident.clone(),
-                snake,
-                fields: var.fields.clone()
-            }
-        }
-    ).collect();
-
-    // generate the fields and impl code
-    let mut field_idents = vec![];
-    let mut field_names = vec![];
-    let mut struct_fields = vec![];
-    let mut get_uncheckeds = vec![];
-    let mut get_mut_uncheckeds = vec![];
-    let mut gets = vec![];
-    let mut get_muts = vec![];
-    let mut new_args = vec![];
-    let mut new_fields = vec![];
-    for VariantInfo { normal, snake, fields } in &vars {
-        field_idents.push(snake.clone());
-        field_names.push(snake.to_string());
-        match fields {
-            Fields::Unit => {
-                struct_fields.push(quote! { pub #snake: T });
-                gets.push(quote! { &#enum_ident::#normal => Some(&self.#snake) });
-                get_muts.push(quote! { &#enum_ident::#normal => Some(&mut self.#snake) });
-                get_uncheckeds.push(quote! { &#enum_ident::#normal => &self.#snake });
-                get_mut_uncheckeds.push(quote! { &#enum_ident::#normal => &mut self.#snake });
-                new_args.push(quote! {#snake: T});
-                new_fields.push(quote! {#snake});
-            }
-            Fields::Unnamed(syn::FieldsUnnamed { unnamed, .. }) => {
-                if unnamed.len() == 1 {
-                    let ty = unnamed.first().unwrap().clone().ty;
-                    struct_fields.push(quote! {
-                        pub #snake: std::collections::HashMap<#ty, T>
-                    });
-                    gets.push(quote! {
-                        &#enum_ident::#normal(key) => self.#snake.get(&key)
-                    });
-                    get_muts.push(quote! {
-                        &#enum_ident::#normal(key) => self.#snake.get_mut(&key)
-                    });
-                    get_uncheckeds.push(quote! {
-                        &#enum_ident::#normal(key) => self.#snake.get(&key)
-                            .expect("tuple variant key not found in hashmap")
-                    });
-                    get_mut_uncheckeds.push(quote! {
-                        &#enum_ident::#normal(key) => self.#snake.get_mut(&key)
-                            .expect("tuple variant key not found in hashmap")
-                    });
-                    new_fields.push(quote! {#snake: std::collections::HashMap::new()});
-                } else {
-                    emit_error!(unnamed, "only tuples with one value are allowed");
-                }
-            }
-            Fields::Named(syn::FieldsNamed { named, .. }) => {
-                if named.len() == 1 {
-                    let ty = named.first().unwrap().clone().ty;
-                    let ident = named.first().unwrap().ident.clone().unwrap();
-                    struct_fields.push(quote! {
-                        pub #snake: std::collections::HashMap<#ty, T>
-                    });
-                    gets.push(quote! {
-                        &#enum_ident::#normal {#ident}  => self.#snake.get(&#ident)
-                    });
-                    get_muts.push(quote! {
-                        &#enum_ident::#normal {#ident}  => self.#snake.get_mut(&#ident)
-                    });
-                    get_uncheckeds.push(quote! {
-                        &#enum_ident::#normal {#ident} => self.#snake.get(&#ident)
-                            .expect("tuple variant key not found in hashmap")
-                    });
-                    get_mut_uncheckeds.push(quote! {
-                        &#enum_ident::#normal {#ident} => self.#snake.get_mut(&#ident)
-                            .expect("tuple variant key not found in hashmap")
-                    });
-                    new_fields.push(quote! {#snake: std::collections::HashMap::new()});
-                } else {
-                    emit_error!(named, "only structs with one field are allowed");
-                }
-            }
-        }
-    }
-
-    // combine it all together
-    (quote! {
-        #[derive(#(#derives),*)]
-        #visibility struct #struct_ident<T: #(#bounds)+*> {
-            #(#struct_fields),*
-        }
-
-        impl<T: #(#bounds)+*> #struct_ident<T> {
-            pub fn new(#(#new_args),*) -> #struct_ident<T> {
-                #struct_ident {
-                    #(#new_fields),*
-                }
-            }
-
-            pub fn get_unchecked(&self, var: &#enum_ident) -> &T {
-                match var {
-                    #(#get_uncheckeds),*
-                }
-            }
-
-            pub fn get_mut_unchecked(&mut self, var: &#enum_ident) -> &mut T {
-                match var {
-                    #(#get_mut_uncheckeds),*
-                }
-            }
-
-            pub fn get(&self, var: &#enum_ident) -> Option<&T> {
-                match var {
-                    #(#gets),*
-                }
-            }
-
-            pub fn get_mut(&mut self, var: &#enum_ident) -> Option<&mut T> {
-                match var {
-                    #(#get_muts),*
-                }
-            }
-        }
-    }).into()
-}
+//!
+//! If you already know which variant you want, there are also accessors named after each hashmap's field, which take
+//! the key instead of a full variant. Like the methods of `HashMap`, they accept any borrowed form of the key, so a
+//! `String` key can be looked up with a `&str`. For the example above, they are:
+//!
+//! ```
+//! # use std::hash::Hash;
+//! # use std::borrow::Borrow;
+//! # struct HelloStruct<T> { there: std::collections::HashMap<i32, T> }
+//! impl<T> HelloStruct<T> {
+//!     pub fn there<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<&T> where i32: Borrow<Q> {
+//!         self.there.get(key)
+//!     }
+//!
+//!     pub fn there_mut<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<&mut T> where i32: Borrow<Q> {
+//!         self.there.get_mut(key)
+//!     }
+//!
+//!     pub fn there_insert(&mut self, key: i32, value: T) -> Option<T> {
+//!         self.there.insert(key, value)
+//!     }
+//!
+//!     pub fn there_remove<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<T> where i32: Borrow<Q> {
+//!         self.there.remove(key)
+//!     }
+//!
+//!     pub fn there_key_value<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<(&i32, &T)> where i32: Borrow<Q> {
+//!         self.there.get_key_value(key)
+//!     }
+//! }
+//! ```
+//!
+//! The `_key_value` accessor returns the key as it's stored in the hashmap along with the value, which is useful when keys
+//! can be equal without being identical. The struct's `get_key_value(&var)` does the same for any variant, and returns the
+//! stored variant rebuilt from its key (so the keys must be `Clone`, and with a `catch_all` field, so must the enum).
+//!
+//! An accessor that would have the same name as one of the other methods is left out, so a variant like `Get(i32)` doesn't
+//! get `get()`, `get_mut()` or `get_key_value()` accessors (its field can still be used directly, or renamed with
+//! `field_name` to get them).
+//!
+//! ## Dense Keys
+//!
+//! If the key of a variant is a small integer, a `HashMap` is mostly overhead. The `dense_key` attribute stores the values
+//! in a `Vec<Option<T>>` instead, which is indexed directly by the key and has room for every key below `max`.
+//! For example, this:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! enum Hello {
+//!     World,
+//!     #[dense_key(max = 64)] There(u8)
+//! }
+//! ```
+//!
+//! produces the following code (unchecked getters omitted):
+//!
+//! ```
+//! # enum Hello {
+//! #     World,
+//! #     There(u8)
+//! # }
+//! struct HelloStruct<T> {
+//!     pub world: T,
+//!     pub there: Vec<Option<T>>
+//! }
+//!
+//! impl<T> HelloStruct<T> {
+//!     fn new(world: T) -> HelloStruct<T> {
+//!         HelloStruct {
+//!             world,
+//!             there: std::iter::repeat_with(|| None).take(64).collect()
+//!         }
+//!     }
+//!
+//!     pub fn get(&self, var: &Hello) -> Option<&T> {
+//!         match var {
+//!             Hello::World => Some(&self.world),
+//!             Hello::There(key) => self.there.get(*key as usize).and_then(Option::as_ref)
+//!         }
+//!     }
+//!
+//!     pub fn get_mut(&mut self, var: &Hello) -> Option<&mut T> {
+//!         match var {
+//!             Hello::World => Some(&mut self.world),
+//!             Hello::There(key) => self.there.get_mut(*key as usize).and_then(Option::as_mut)
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Keys that are `max` or above are never stored, so the checked getters return `None` for them.
+//!
+//! If the keys are known ahead of time, like the keywords of a language, they can be listed with `keys` instead of
+//! `max`. The list can contain string or integer literals, and the slots are stored inline in an `[Option<T>; N]`
+//! with one slot per key. A key is turned into its index with a `match` on the list, which compares it with each key
+//! in turn. This isn't a perfect hash, but it doesn't hash or allocate anything, which is faster than a `HashMap` for
+//! short lists:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! enum Token {
+//!     Identifier,
+//!     #[dense_key(keys("if", "else", "while"))] Keyword(&'static str)
+//! }
+//!
+//! let mut tokens = TokenStruct::new(0);
+//! tokens.keyword_insert("else", 1);
+//! assert_eq!(tokens.get(&Token::Keyword("else")), Some(&1));
+//! assert_eq!(tokens.get(&Token::Keyword("for")), None);
+//! ```
+//!
+//! Keys that aren't in the list are never stored, just like the keys above `max`.
+//!
+//! Adding `array` to a `max` (like `#[dense_key(max = 8, array)]`) stores its slots in an `[Option<T>; N]` inside the
+//! struct too, instead of in a `Vec`. The getters are the same either way.
+//!
+//! ## Multiple Values
+//!
+//! To keep several values for each key, the `multi` attribute stores a `HashMap<K, Vec<T>>` instead. `push()` adds
+//! a value for a variant, and `get_all()` returns all of them as a slice:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! enum Hello {
+//!     World,
+//!     #[multi] There(i32)
+//! }
+//!
+//! let mut hello = HelloStruct::new(0);
+//! hello.push(Hello::There(5), 1);
+//! hello.push(Hello::There(5), 2);
+//! assert_eq!(hello.get_all(&Hello::There(5)), &[1, 2]);
+//! assert_eq!(hello.get(&Hello::There(5)), Some(&1));
+//! ```
+//!
+//! The other methods keep working on single values: `get()` and `get_mut()` return the first value of a key, and the
+//! iterators, `len()` and `to_vec()` go through every value separately. `push()` and `get_all()` also work for the other
+//! variants, which just have at most one value, so `push()` sets it. The accessors of a multi field are the same,
+//! except that `there()` returns a slice, `there_mut()` returns the whole `Vec`, and `there_insert()` pushes.
+//!
+//! ## Prefilled Keys
+//!
+//! Keys that must always have a value can be given to the `prefill_keys` attribute, either listed or with `keys = ...`
+//! (anything that can be iterated over). `new()`, `with_capacity()` and `new_default()` fill them in with `T::default()`.
+//! If `Default` is in `struct_derive`, it isn't derived, but implemented with `new_default()` so that it fills them in
+//! too:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! const LANGUAGES: [&str; 2] = ["en", "fr"];
+//!
+//! #[derive(VariantsStruct)]
+//! enum Message {
+//!     Id,
+//!     #[prefill_keys(keys = LANGUAGES)] Text(&'static str)
+//! }
+//!
+//! let messages: MessageStruct<String> = MessageStruct::new(String::from("greeting"));
+//! assert_eq!(messages.text["fr"], "");
+//! ```
+//!
+//! A different value can be given with `value = ...`, like `#[prefill_keys("en", "fr", value = T::from(key))]`. It's
+//! evaluated once for each key, which it can use as `key`, and it can't use the arguments of `new()`. Without a `value`,
+//! the constructors need `T: Default`. The keys are only filled in when the struct is made, so `clear()` still removes
+//! them, and `Deserialize` (derived, or from `serde_pairs`) only has the keys that it reads.
+//!
+//! # Fallbacks
+//!
+//! `get_with_fallback()` tries several variants in order and returns the first value that is present, and
+//! `get_or_else()` calls a closure if a variant isn't present:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! enum Language {
+//!     English,
+//!     Regional(&'static str)
+//! }
+//!
+//! let mut greetings = LanguageStruct::new("hello");
+//! greetings.regional.insert("en-GB", "hiya");
+//!
+//! let greeting = greetings.get_with_fallback(&[
+//!     Language::Regional("en-AU"),
+//!     Language::Regional("en-GB"),
+//!     Language::English
+//! ]);
+//! assert_eq!(greeting, Some(&"hiya"));
+//! assert_eq!(*greetings.get_or_else(&Language::Regional("fr"), || &"bonjour"), "bonjour");
+//! ```
+//!
+//! To look up many variants at once, `gather()` returns all of their values (or `None`) in order:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! # #[derive(VariantsStruct)]
+//! # enum Language {
+//! #     English,
+//! #     Regional(&'static str)
+//! # }
+//! # let mut greetings = LanguageStruct::new("hello");
+//! # greetings.regional.insert("en-GB", "hiya");
+//! let found = greetings.gather(vec![Language::Regional("en-GB"), Language::Regional("fr"), Language::English]);
+//! assert_eq!(found, vec![Some(&"hiya"), None, Some(&"hello")]);
+//! ```
+//!
+//! ## Layering Defaults
+//!
+//! `apply_defaults()` fills in the keys that a struct doesn't store from another struct, so configuration can be
+//! layered from several sources. Unit variants always have a value, so `apply_defaults_where()` also replaces the
+//! values for which a closure returns `true`, like the `None`s in a struct of `Option`s:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! enum Setting {
+//!     Theme,
+//!     Path(&'static str)
+//! }
+//!
+//! let mut defaults = SettingStruct::new(Some("light"));
+//! defaults.path.insert("home", Some("~"));
+//! defaults.path.insert("temp", Some("/tmp"));
+//!
+//! let mut settings = SettingStruct::new(None);
+//! settings.path.insert("home", Some("/home/me"));
+//! settings.apply_defaults_where(defaults, Option::is_none);
+//!
+//! assert_eq!(settings.theme, Some("light"));
+//! assert_eq!(settings.path["home"], Some("/home/me"));
+//! assert_eq!(settings.path["temp"], Some("/tmp"));
+//! ```
+//!
+//! # Mapping
+//!
+//! `map_ref()` builds a new struct by calling a function on a reference to every value, without consuming the
+//! original struct. Like iterating, this requires the keys to be `Clone`:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! enum Hello {
+//!     World,
+//!     There(i32)
+//! }
+//!
+//! let mut hello = HelloStruct::new("hello".to_string());
+//! hello.there.insert(5, "there".to_string());
+//!
+//! let lengths = hello.map_ref(|value| value.len());
+//! assert_eq!(lengths.world, 5);
+//! assert_eq!(lengths.there[&5], 5);
+//! ```
+//!
+//! The function can also return something borrowed from the value, like `hello.map_ref(String::as_str)`, and
+//! `each_ref()` is a shorthand for a struct of references to every value.
+//!
+//! ## Snapshots
+//!
+//! `snapshot()` clones the whole struct (even if it doesn't derive `Clone`), and `restore()` puts a snapshot back in
+//! place, which is handy for undoing changes:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! # #[derive(VariantsStruct)]
+//! # enum Hello {
+//! #     World,
+//! #     There(i32)
+//! # }
+//! let mut hello = HelloStruct::new(1);
+//! let snapshot = hello.snapshot();
+//! hello.world = 2;
+//! hello.there.insert(5, 3);
+//!
+//! hello.restore(snapshot);
+//! assert_eq!(hello.world, 1);
+//! assert!(hello.there.is_empty());
+//! ```
+//!
+//! # Random Values
+//!
+//! With the `random` attribute, the struct can be sampled from `rand`'s `Standard` distribution. The generated code
+//! uses the paths of `rand` 0.8 (`rand::distributions::{Distribution, Standard}` and `rand::Rng`), and your crate has to
+//! depend on it. If it's known by another name (like a renamed dependency or a re-export), that path can be given with
+//! `#[random(crate = "...")]`. Every unit variant gets a sampled value, and the other variants start out empty:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! use rand::Rng;
+//!
+//! #[derive(VariantsStruct)]
+//! #[random]
+//! enum Hello {
+//!     World,
+//!     There(i32)
+//! }
+//!
+//! let hello: HelloStruct<u8> = rand::thread_rng().gen();
+//! assert!(hello.there.is_empty());
+//! ```
+//!
+//! # Transposing
+//!
+//! A struct of `Option`s or `Result`s can be turned into an `Option` or `Result` of a struct with `transpose()`.
+//! This returns `None` if any value is `None`, or the first error found:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! enum Hello {
+//!     World,
+//!     There(i32)
+//! }
+//!
+//! let mut parsed = HelloStruct::new("1".parse::<i32>());
+//! parsed.there.insert(5, "2".parse());
+//! let hello = parsed.transpose().unwrap();
+//! assert_eq!(hello.world, 1);
+//! assert_eq!(hello.there[&5], 2);
+//!
+//! let mut parsed = HelloStruct::new(Some(1));
+//! parsed.there.insert(5, None);
+//! assert!(parsed.transpose().is_none());
+//! ```
+//!
+//! The empty slots of `dense_key` variants stay empty, and aren't counted as missing values.
+//!
+//! ## Rows and Columns
+//!
+//! If the enum only has unit variants, a struct of `Vec`s can be split into a `Vec` of structs with `into_rows()`,
+//! which stops at the shortest `Vec`. The other way around, a struct of `Vec`s can be collected from structs:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[struct_derive(Debug, PartialEq)]
+//! enum Hello {
+//!     World,
+//!     There
+//! }
+//!
+//! let columns = HelloStruct::new(vec![1, 2], vec![3, 4]);
+//! let rows = columns.into_rows();
+//! assert_eq!(rows, vec![HelloStruct::new(1, 3), HelloStruct::new(2, 4)]);
+//!
+//! let columns: HelloStruct<Vec<i32>> = rows.into_iter().collect();
+//! assert_eq!(columns, HelloStruct::new(vec![1, 2], vec![3, 4]));
+//! ```
+//!
+//! # Indexing
+//!
+//! The struct can also be indexed by a variant (or a reference to one), which works like the unchecked getters:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! enum Hello {
+//!     World,
+//!     There(i32)
+//! }
+//!
+//! let mut hello = HelloStruct::new(1);
+//! hello.there.insert(5, 2);
+//! hello[Hello::World] += 10;
+//! assert_eq!(hello[&Hello::There(5)], 2);
+//! ```
+//!
+//! Indexing a key that isn't stored panics. With the `auto_insert` attribute, `index_mut()` and `get_mut_unchecked()`
+//! insert `T::default()` for missing keys instead (like `entry(key).or_default()`), which is useful for counters. They
+//! then require `T: Default`, and keys have to be `Clone` to be inserted:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[auto_insert]
+//! enum Event {
+//!     Load,
+//!     Click(u32)
+//! }
+//!
+//! let mut counts = EventStruct::new(0);
+//! counts[Event::Click(3)] += 1;
+//! counts[Event::Click(3)] += 1;
+//! assert_eq!(counts[Event::Click(3)], 2);
+//! ```
+//!
+//! Reading through `index()` or `get_unchecked()` still panics for missing keys, and `dense_key` variants still panic
+//! for keys that are `max` or above.
+//!
+//! To check ahead of time whether a variant can be missing, the `getter_table` attribute (see
+//! [Getter Tables](#getter-tables)) adds `is_map_backed()`, which returns whether it's stored by its key:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! # #[derive(VariantsStruct)]
+//! # #[getter_table]
+//! # enum Hello {
+//! #     World,
+//! #     There(i32)
+//! # }
+//! assert!(!HelloStruct::<i32>::is_map_backed(&Hello::World));
+//! assert!(HelloStruct::<i32>::is_map_backed(&Hello::There(5)));
+//! ```
+//!
+//! ## By Name
+//!
+//! When the variant is only known as a string at runtime (like in a console), `get_by_name()` and `get_by_name_mut()`
+//! look up a unit variant by the name of its field:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! enum Hello {
+//!     World,
+//!     ThereAgain
+//! }
+//!
+//! let mut hello = HelloStruct::new(1, 2);
+//! *hello.get_by_name_mut("there_again").unwrap() += 1;
+//! assert_eq!(hello.get_by_name("there_again"), Some(&3));
+//! assert_eq!(hello.get_by_name("ThereAgain"), None);
+//! ```
+//!
+//! The names are the generated field names, so they follow `field_name` and `rename_fields`. Variants with keys
+//! (and merged enums) can't be looked up by name.
+//!
+//! ## Getter Tables
+//!
+//! For code that picks fields at runtime (like a scripting layer), the `getter_table` attribute generates the
+//! `GETTERS` and `GETTERS_MUT` consts, which hold a function pointer for each unit variant in the same order as
+//! `iter_unit()`. `unit_index()` gives a variant's position in them, or `None` if it's stored by its key, and
+//! `is_map_backed()` tells the two kinds of variants apart:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[getter_table]
+//! enum Hello {
+//!     World,
+//!     There(i32)
+//! }
+//!
+//! let mut hello = HelloStruct::new(1);
+//! let index = HelloStruct::<i32>::unit_index(&Hello::World).unwrap();
+//! *HelloStruct::GETTERS_MUT[index](&mut hello) += 1;
+//! assert_eq!(*HelloStruct::GETTERS[index](&hello), 2);
+//! assert_eq!(HelloStruct::<i32>::unit_index(&Hello::There(5)), None);
+//! ```
+//!
+//! # Iteration
+//!
+//! `iter()` and `iter_mut()` go through every stored value along with its variant, in the same order as `to_vec()`.
+//! They return the `HelloStructIter` and `HelloStructIterMut` types (named after the struct), which are also the iterators
+//! used for `&HelloStruct<T>` and `&mut HelloStruct<T>` in `for` loops:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct, Debug)]
+//! enum Hello {
+//!     World,
+//!     There(i32)
+//! }
+//!
+//! let mut hello = HelloStruct::new(1);
+//! hello.there.insert(5, 2);
+//!
+//! for (var, value) in &mut hello {
+//!     *value *= 10;
+//! }
+//! for (var, value) in &hello {
+//!     println!("{:?} is {}", var, value);
+//! }
+//! ```
+//!
+//! Since the variants are built from the stored keys, the keys must be `Clone` to iterate over the struct.
+//!
+//! To handle the two kinds of variants separately, `iter_unit()` and `iter_unit_mut()` only go through the unit variants
+//! (which always have a value), and `iter_map()` and `iter_map_mut()` only go through the entries of the hashmaps.
+//!
+//! `invert()` builds a reverse index from each stored value to the variants that hold it (in iteration order), when
+//! `T: Hash + Eq`:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct, PartialEq, Debug)]
+//! enum Hello {
+//!     World,
+//!     There(i32)
+//! }
+//!
+//! let mut hello = HelloStruct::new('a');
+//! hello.there.insert(5, 'a');
+//! hello.there.insert(6, 'b');
+//!
+//! let inverted = hello.invert();
+//! assert_eq!(inverted[&'a'], vec![Hello::World, Hello::There(5)]);
+//! assert_eq!(inverted[&'b'], vec![Hello::There(6)]);
+//! ```
+//!
+//! The iterators always implement `FusedIterator`. Unless there are `dense_key` or `multi` variants (which have to skip
+//! over empty slots or go through several values per key) they also implement `ExactSizeIterator`, and unless there are
+//! hashmaps (which have no order to reverse) they also implement `DoubleEndedIterator`.
+//!
+//! Every iterator is a named type, so it can be stored in a struct or used in the signature of a trait impl: `iter_map()`
+//! returns `HelloStructMapIter` (and `HelloStructMapIterMut`), `into_values()` returns `HelloStructIntoValues`, and
+//! `drain()` returns `HelloStructDrain`. `iter_unit()` returns a `std::array::IntoIter`.
+//!
+//! These types are always defined next to the struct, so other items in the same module can't have their names. This is a
+//! breaking change from version 0.1, where the struct was the only item generated.
+//!
+//! ## Ordered Keys
+//!
+//! The values in a `HashMap` are in an arbitrary order, which changes between runs. With the `ordered_maps` attribute,
+//! the variants with keys (including `multi` variants and `catch_all`) are stored in `BTreeMap`s instead, so iteration,
+//! `Debug`, and serialization always go through them in the order of their keys:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[struct_derive(Debug)]
+//! #[ordered_maps]
+//! enum Hello {
+//!     World,
+//!     There(i32)
+//! }
+//!
+//! let mut hello = HelloStruct::new(0);
+//! hello.there_insert(5, 1);
+//! hello.there_insert(2, 2);
+//! assert_eq!(hello.iter().map(|(_, value)| *value).collect::<Vec<_>>(), vec![0, 2, 1]);
+//! assert_eq!(format!("{:?}", hello), "HelloStruct { world: 0, there: {2: 2, 5: 1} }");
+//! ```
+//!
+//! The keys then have to implement `Ord` instead of `Hash` and `Eq`. A `BTreeMap` has no capacity, so `with_capacity()`,
+//! `reserve()`, and `shrink_to_fit()` don't do anything for it.
+//!
+//! ## Visitors
+//!
+//! To handle each variant differently, add the `visitor` attribute, and implement the `HelloStructVisitor<T>` trait that
+//! it generates and pass it to `accept()`. The trait has a `visit_{field}()` method for each field, which is called with
+//! each of its values (and their keys), and does nothing unless it's implemented:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[visitor]
+//! enum Hello {
+//!     World,
+//!     There(i32)
+//! }
+//!
+//! struct Sum(i32);
+//!
+//! impl HelloStructVisitor<i32> for Sum {
+//!     fn visit_there(&mut self, key: &i32, value: &i32) {
+//!         self.0 += key * value;
+//!     }
+//! }
+//!
+//! let mut hello = HelloStruct::new(1);
+//! hello.there_insert(2, 3);
+//! hello.there_insert(4, 5);
+//! let mut sum = Sum(0);
+//! hello.accept(&mut sum);
+//! assert_eq!(sum.0, 26);
+//! ```
+//!
+//! The fields are visited in order. A merged enum's struct is passed to its method as a whole, and the catch-all field
+//! has a method that is called with the variant of each value.
+//!
+//! ## Validation
+//!
+//! `validate_all()` checks every value with a closure, and instead of stopping at the first error, returns all of
+//! them along with the names of their fields. Like the iterators, it needs the keys to be `Clone`:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! enum Limit {
+//!     Global,
+//!     User(&'static str)
+//! }
+//!
+//! let mut limits = LimitStruct::new(0);
+//! limits.user_insert("ann", 10);
+//! let result = limits.validate_all(|_, value| if *value > 0 { Ok(()) } else { Err("must be positive") });
+//! assert_eq!(result, Err(vec![(String::from("global"), "must be positive")]));
+//! ```
+//!
+//! # Merging Enums
+//!
+//! The variants structs of other enums can be included in this one with `merge_with`, to keep several enums in one
+//! table. Each merged struct is stored in a field named after its enum, and gets its own getters:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! enum Color {
+//!     Red,
+//!     Blue
+//! }
+//!
+//! #[derive(VariantsStruct)]
+//! #[merge_with(Color)]
+//! enum Shape {
+//!     Circle,
+//!     Square
+//! }
+//!
+//! let mut counts = ShapeStruct::new(0, 0, ColorStruct::new(0, 0));
+//! *counts.get_color_mut(&Color::Red).unwrap() += 1;
+//! assert_eq!(counts.get_color(&Color::Red), Some(&1));
+//! assert_eq!(counts.color.red, 1);
+//! ```
+//!
+//! If a merged enum's struct was renamed, it has to be given like `merge_with(Color = "Colors")`. The merged structs
+//! must have the same `struct_bounds`, and are cleared and reset along with this struct. The other methods (like
+//! `len()` and the iterators) only go through this enum's own variants.
+//!
+//! # Handlers
+//!
+//! With the `handlers` attribute, `HelloStructHandlers<Args, Out>` (or the name given with `handlers = "..."`) is a
+//! variants struct of boxed functions, which can be used as a dispatch table. `call()` calls the handler of a variant
+//! (and panics if there isn't one, like `get_unchecked()`), and `try_call()` returns `None` instead. The functions have
+//! to be `Send + Sync`, so the table can be shared between threads:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[handlers]
+//! enum Op {
+//!     Neg,
+//!     Add(i32)
+//! }
+//!
+//! let mut handlers: OpStructHandlers<i32, i32> = OpStructHandlers::new(Box::new(|x| -x));
+//! handlers.add_insert(5, Box::new(|x| x + 5));
+//!
+//! assert_eq!(handlers.call(&Op::Neg, 3), -3);
+//! assert_eq!(handlers.call(&Op::Add(5), 3), 8);
+//! assert_eq!(handlers.try_call(&Op::Add(6), 3), None);
+//! ```
+//!
+//! # Statics
+//!
+//! If the enum only has unit variants (and the values aren't boxed), `new()` is a `const fn`, so the struct can be used
+//! in a `static` or `const`:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! enum Hello {
+//!     World,
+//!     There
+//! }
+//!
+//! static GREETINGS: HelloStruct<&str> = HelloStruct::new("hello world", "hello there");
+//! assert_eq!(GREETINGS.get_unchecked(&Hello::There), &"hello there");
+//! ```
+//!
+//! Hashmaps can't be created in a const context, so a struct with tuple or struct variants has to be initialized
+//! lazily, like with `std::sync::OnceLock`:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! use std::sync::OnceLock;
+//!
+//! #[derive(VariantsStruct)]
+//! enum Token {
+//!     Eof,
+//!     Keyword(&'static str)
+//! }
+//!
+//! fn tokens() -> &'static TokenStruct<u8> {
+//!     static TOKENS: OnceLock<TokenStruct<u8>> = OnceLock::new();
+//!     TOKENS.get_or_init(|| {
+//!         let mut tokens = TokenStruct::new(0);
+//!         tokens.keyword_insert("fn", 1);
+//!         tokens.keyword_insert("let", 2);
+//!         tokens
+//!     })
+//! }
+//! assert_eq!(tokens()[Token::Keyword("let")], 2);
+//! ```
+//!
+//! # Sharing Between Threads
+//!
+//! For a struct that is read from many threads and only updated now and then, the `cell` attribute generates
+//! `HelloStructCell<T>` (or the name given with `cell = "..."`). It holds the struct in an `Arc`: `load()` returns
+//! the current one, and `store()`, `swap()`, and `rcu()` replace it with a new one, so readers never see an update
+//! halfway through. `rcu()` makes the new struct from the current one, and tries again if another thread stored one first.
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[struct_derive(Clone)]
+//! #[cell]
+//! enum Limit {
+//!     Connections,
+//!     Requests
+//! }
+//!
+//! let limits = LimitStructCell::new(LimitStruct::new(10, 100));
+//! let before = limits.load();
+//! limits.rcu(|current| {
+//!     let mut next = current.clone();
+//!     next.requests *= 2;
+//!     next
+//! });
+//! assert_eq!(before.requests, 100);
+//! assert_eq!(limits.load().requests, 200);
+//! ```
+//!
+//! The `Arc` is swapped behind a `RwLock`, which is only held long enough to clone or replace it, so this is cheap but
+//! not lock-free.
+//!
+//! ## Async Tasks
+//!
+//! For a struct that is shared between async tasks, the `async_locks` attribute generates `HelloStructLocks<T>`, which
+//! keeps each value behind its own `tokio::sync::RwLock` (your crate has to depend on `tokio` 1.x with its `sync`
+//! feature). `read(var)` and `write(var)` wait for the variant's lock, so tasks that use different values don't wait
+//! for each other:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[async_locks]
+//! enum Limit {
+//!     Connections,
+//!     Requests
+//! }
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let limits = std::sync::Arc::new(LimitStructLocks::new(LimitStruct::new(10, 100)));
+//! *limits.write(&Limit::Requests).await *= 2;
+//! assert_eq!(*limits.read(&Limit::Requests).await, 200);
+//! # }
+//! ```
+//!
+//! With `async_locks(mutex)`, the values are kept behind `tokio::sync::Mutex`es instead, and are locked with
+//! `lock(var)`. `into_inner()` takes the values back out into a `HelloStruct<T>`. Since every value needs its own
+//! lock, this can only be used on enums with only unit variants. If `tokio` is known by another name (like a renamed
+//! dependency or a re-export), that path can be given with `crate = "..."`, like `#[async_locks(mutex, crate = "rt")]`.
+//!
+//! # Large Enums
+//!
+//! Every getter matches on the variant, which can generate a lot of code for enums with hundreds of variants. If the
+//! enum only has unit variants, the `jump_table` attribute stores all of the values in one array instead, and the
+//! getters convert the variant to an index into it. The index is found by casting the variant to its discriminant, so
+//! the enum has to be `Copy`:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct, Clone, Copy)]
+//! #[jump_table]
+//! enum Hello {
+//!     World,
+//!     There
+//! }
+//!
+//! let mut hello = HelloStruct::new("hello world", "hello there");
+//! *hello.get_mut_unchecked(&Hello::World) = "goodbye world";
+//! assert_eq!(hello.values, ["goodbye world", "hello there"]);
+//! ```
+//!
+//! The values are in the order of the fields (which is the order of the variants, unless it's changed with
+//! `field_order`), and the struct has no named fields. If the discriminants aren't the positions of the fields (because
+//! some are given explicitly, or the fields are reordered), a const table maps them to the positions. It has a slot for
+//! every value between the smallest and the biggest discriminant, so they shouldn't be spread far apart. Some derives (like `Default` and `Serialize`) are only
+//! implemented for arrays of up to 32 elements, so they can't be used with bigger enums.
+//!
+//! # Memory Usage
+//!
+//! `heap_usage()` estimates the heap memory held by the struct in bytes, from the capacity of its hashmaps and dense
+//! storage. It doesn't know about memory that the values hold themselves, so `heap_usage_with()` takes a closure that
+//! returns it for each value:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! enum Hello {
+//!     World,
+//!     There(i32)
+//! }
+//!
+//! let mut hello = HelloStruct::new(String::from("world"));
+//! hello.there_insert(1, String::from("there"));
+//! let held = hello.heap_usage_with(String::capacity);
+//! assert!(held >= hello.heap_usage() + "world".len() + "there".len());
+//! ```
+//!
+//! The estimate doesn't include the hashmaps' own bookkeeping, or the memory held by the keys.
+//!
+//! # Large Values
+//!
+//! If the values are big (like buffers of several kilobytes), a struct of them is expensive to move, and might not
+//! even fit on the stack. The `boxed_fields` attribute stores the value of each unit variant in a `Box<T>` instead,
+//! while the getters and iterators still use `&T` and `&mut T`:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[boxed_fields]
+//! enum Buffer {
+//!     Front,
+//!     Back
+//! }
+//!
+//! let mut buffers = BufferStruct::new([0u8; 4096], [0; 4096]);
+//! buffers.get_mut_unchecked(&Buffer::Back)[0] = 1;
+//! let back: &[u8; 4096] = buffers.get_unchecked(&Buffer::Back);
+//! assert_eq!(back[0], 1);
+//! ```
+//!
+//! The fields themselves are boxes, so `buffers.front` is a `Box<[u8; 4096]>`. The values of tuple and struct variants
+//! are already on the heap, and a jump table can't be boxed.
+//!
+//! # Avoiding Allocation
+//!
+//! With the `no_alloc` attribute, the derive refuses anything that would keep values on the heap: variants stored in a
+//! `HashMap` (including `multi` variants), dense keys without `array`, `catch_all`, and `cell`. Unit variants and
+//! dense keys with `array` are stored inline, so the whole struct can live on the stack:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[no_alloc]
+//! enum Register {
+//!     ProgramCounter,
+//!     #[dense_key(max = 4, array)] General(u8)
+//! }
+//!
+//! let mut registers = RegisterStruct::new(0);
+//! registers.general_insert(2, 7);
+//! assert_eq!(registers.general, [None, None, Some(7), None]);
+//! ```
+//!
+//! The attribute only checks the fields of the struct: methods that return a `Vec` (like `to_vec()`) still allocate,
+//! and a merged struct is only checked if its own enum is `no_alloc` too. Like with `jump_table`, derives such as
+//! `Default` and `Serialize` only work on arrays of up to 32 slots.
+//!
+//! # Marker Types
+//!
+//! With the `markers` attribute, every variant also gets a marker type in a module named after the enum (or the name
+//! given with `markers = "..."`). `get_typed()`, `get_typed_mut()`, and `set_typed()` use these to access a field that
+//! is known at compile time, without matching on a variant. The markers implement `HelloMarker<T>`, whose `Field` type
+//! is the type of the variant's whole field:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[markers]
+//! enum Hello {
+//!     World,
+//!     There(i32)
+//! }
+//!
+//! let mut hello = HelloStruct::new(1);
+//! *hello.get_typed_mut::<hello::World>() += 1;
+//! hello.get_typed_mut::<hello::There>().insert(5, 3);
+//!
+//! assert_eq!(*hello.get_typed::<hello::World>(), 2);
+//! assert_eq!(hello.get_typed::<hello::There>()[&5], 3);
+//! ```
+//!
+//! # Compile-Time Iteration
+//!
+//! With the `for_each_macro` attribute, a `macro_rules!` macro named `for_each_hello_variant!` (after the enum, or the
+//! name given with `for_each_macro = "..."`) is also generated, which calls another macro once for each variant with the
+//! variant's name and field. Any tokens after the macro's name are passed along too, which is useful for generating code
+//! for each variant:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[for_each_macro]
+//! enum Hello {
+//!     World,
+//!     There(i32)
+//! }
+//!
+//! trait Describe {
+//!     fn describe() -> &'static str;
+//! }
+//!
+//! macro_rules! describe_variant {
+//!     ($variant:ident, $field:ident, $prefix:literal) => {
+//!         struct $variant;
+//!         impl Describe for $variant {
+//!             fn describe() -> &'static str {
+//!                 concat!($prefix, stringify!($variant), " is stored in ", stringify!($field))
+//!             }
+//!         }
+//!     };
+//! }
+//!
+//! for_each_hello_variant!(describe_variant, "variant ");
+//! assert_eq!(There::describe(), "variant There is stored in there");
+//! ```
+//!
+//! Since it's a `macro_rules!` macro, it can only be used after the enum, in the same module or its children.
+//!
+//! # Tables
+//!
+//! A table indexed by two enums can be generated with the `table` attribute, which names the enum of the columns.
+//! That enum needs its own variants struct, and the table stores one of those for each variant of this enum. The
+//! table is named `{Enum}{Cols}Table` unless it's given a `name`:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct, Clone, Copy)]
+//! #[table(cols = "Element", name = "Chart")]
+//! enum Element {
+//!     Fire,
+//!     Water
+//! }
+//!
+//! let chart = Chart::new(ElementStruct::new(
+//!     ElementStruct::new(1.0, 0.5),
+//!     ElementStruct::new(2.0, 1.0)
+//! ));
+//! assert_eq!(chart.get(&Element::Water, &Element::Fire), Some(&2.0));
+//! assert_eq!(chart[(Element::Fire, Element::Water)], 0.5);
+//! ```
+//!
+//! If the columns' struct was renamed, it has to be given with `cols_struct = "..."`.
+//!
+//! # Attribute Macro
+//!
+//! The struct can also be generated with the `variants_struct` attribute instead of the derive, which takes the same
+//! attributes. Since an attribute macro can add to the enum, this also gives the enum constructors for the struct:
+//! `table()` takes the same arguments as `new()`, and `table_default()` uses `T::default()` for every unit variant.
+//!
+//! ```
+//! use variants_struct::variants_struct;
+//!
+//! #[variants_struct]
+//! #[struct_name = "Greetings"]
+//! enum Hello {
+//!     World,
+//!     #[field_name = "others"] There(i32)
+//! }
+//!
+//! let greetings: Greetings<String> = Hello::table_default();
+//! assert!(greetings.world.is_empty());
+//! let greetings = Hello::table(5);
+//! assert_eq!(greetings.world, 5);
+//! ```
+//!
+//! # Remote Enums
+//!
+//! To generate a struct for an enum from another crate, which can't be derived on, copy its definition into
+//! `variants_struct_for!` and name it by its path. All of the attributes can be used as usual:
+//!
+//! ```
+//! use variants_struct::variants_struct_for;
+//!
+//! variants_struct_for! {
+//!     #[struct_derive(Debug)]
+//!     pub enum std::cmp::Ordering {
+//!         Less,
+//!         Equal,
+//!         Greater
+//!     }
+//! }
+//!
+//! let mut counts = OrderingStruct::new(0, 0, 0);
+//! for (a, b) in [(1, 2), (2, 2), (3, 2)] {
+//!     counts[a.cmp(&b)] += 1;
+//! }
+//! assert_eq!(counts.equal, 1);
+//! ```
+//!
+//! The definition has to match the real enum, or the generated code won't compile. Since the enum isn't local,
+//! `repr_helpers` can't be used with it.
+//!
+//! # Non-Exhaustive Enums
+//!
+//! Matching on a `#[non_exhaustive]` enum from another crate requires a wildcard arm, since new variants can be added to it
+//! later. The `catch_all` attribute adds a `HashMap<Enum, T>` field (named `catch_all`, or the name given with
+//! `#[catch_all = "..."]`), and routes any variant that the struct doesn't know about to it. For example, this:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct, PartialEq, Eq, Hash)]
+//! #[non_exhaustive]
+//! #[catch_all = "other"]
+//! enum Hello {
+//!     World,
+//!     There
+//! }
+//! ```
+//!
+//! produces the following code (getters omitted):
+//!
+//! ```
+//! # #[derive(PartialEq, Eq, Hash)]
+//! # enum Hello {
+//! #     World,
+//! #     There
+//! # }
+//! struct HelloStruct<T> {
+//!     pub world: T,
+//!     pub there: T,
+//!     pub other: std::collections::HashMap<Hello, T>
+//! }
+//!
+//! impl<T> HelloStruct<T> {
+//!     pub fn get(&self, var: &Hello) -> Option<&T> {
+//!         match var {
+//!             Hello::World => Some(&self.world),
+//!             Hello::There => Some(&self.there),
+//!             #[allow(unreachable_patterns)]
+//!             other => self.other.get(other)
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Since the enum itself is the key, it must implement `Hash` and `Eq`.
+//!
+//! # Discriminants
+//!
+//! If a fieldless enum has a primitive representation (like `#[repr(u8)]`), the `repr_helpers` attribute generates
+//! conversions between the variants and their discriminants on the enum. These use the discriminant values rather than
+//! the declaration order, so they stay the same when the variants are reordered. For example, this:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[repr(u8)]
+//! #[repr_helpers]
+//! enum Hello {
+//!     World = 4,
+//!     There = 2
+//! }
+//! ```
+//!
+//! produces the following code, along with the struct:
+//!
+//! ```
+//! # #[repr(u8)]
+//! # enum Hello {
+//! #     World = 4,
+//! #     There = 2
+//! # }
+//! impl Hello {
+//!     fn as_repr(&self) -> u8 {
+//!         match self {
+//!             Hello::World => Hello::World as u8,
+//!             Hello::There => Hello::There as u8
+//!         }
+//!     }
+//!
+//!     fn from_repr(repr: u8) -> Option<Hello> {
+//!         match repr {
+//!             r if r == Hello::World as u8 => Some(Hello::World),
+//!             r if r == Hello::There as u8 => Some(Hello::There),
+//!             _ => None
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! To keep positions stable too (like the array of a `jump_table`, `GETTERS`, or the order of serialized fields), add
+//! `field_order(discriminant)`, which sorts the fields by the same values. A variant can then be moved without changing
+//! the layout, and a new variant only shifts the fields with bigger discriminants.
+//!
+//! # Enums of Struct Fields
+//!
+//! The `StructVariants` derive goes the other way: it generates an enum with a variant for each field of a struct,
+//! named after the field in PascalCase. If all fields have the same type, the struct also gets `get()` and
+//! `get_mut()` to access them through the enum:
+//!
+//! ```
+//! use variants_struct::StructVariants;
+//!
+//! #[derive(StructVariants)]
+//! struct Size {
+//!     width: u32,
+//!     #[variant_name = "Tall"] height: u32
+//! }
+//!
+//! let mut size = Size { width: 3, height: 4 };
+//! *size.get_mut(SizeField::Tall) += 1;
+//! assert_eq!(*size.get(SizeField::Width), 3);
+//! assert_eq!(size.height, 5);
+//! ```
+//!
+//! The enum is named `{Struct}Field` and derives `Clone`, `Copy`, `PartialEq`, `Eq`, `Hash`, and `Debug`. It can be
+//! renamed with `#[enum_name = "..."]`, and more derives can be added with `#[enum_derive(...)]` (like `struct_name`
+//! and `struct_derive`).
+//!
+//! # Empty Enums
+//!
+//! An enum with no variants produces a struct that only holds a `PhantomData<T>`, so it still takes the type argument
+//! and has all of the same methods:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! enum Nothing {}
+//!
+//! let nothing: NothingStruct<i32> = NothingStruct::new();
+//! ```
+//!
+//! # Lints
+//!
+//! The generated trait impls are marked `#[automatically_derived]`, and the lints that the generated code would trigger
+//! (like `non_snake_case` for fields named like their variants, or `clippy::too_many_arguments` for `new()` on big
+//! enums) are allowed where they apply, so that crates with `#![deny(warnings)]` still compile. The `keep_lints`
+//! attribute leaves all of these out:
+//!
+//! ```
+//! # use variants_struct::VariantsStruct;
+//! #[derive(VariantsStruct)]
+//! #[keep_lints]
+//! enum Hello {
+//!     World,
+//!     There
+//! }
+//! ```
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, ItemEnum, Fields, parse::{Parse, ParseStream}, ext::IdentExt};
+use quote::{quote, format_ident};
+use inflector::Inflector;
+use proc_macro_error::{proc_macro_error, emit_error};
+
+mod expand;
+
+use expand::{expand, parse_ident_lit};
+
+/// The input of `variants_struct_for!`, which is an enum definition that is named by the path of the enum.
+struct RemoteEnum {
+    /// The definition, named after the last segment of the path.
+    item: ItemEnum,
+    path: syn::Path
+}
+
+impl Parse for RemoteEnum {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(syn::Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let enum_token = input.parse()?;
+        let path = input.call(syn::Path::parse_mod_style)?;
+        let content;
+        let brace_token = syn::braced!(content in input);
+        let variants = content.parse_terminated(syn::Variant::parse)?;
+        let ident = path.segments.last().unwrap().ident.clone();
+        Ok(RemoteEnum {
+            item: ItemEnum { attrs, vis, enum_token, ident, generics: Default::default(), brace_token, variants },
+            path
+        })
+    }
+}
+
+/// Derives the variants struct and impl.
+#[proc_macro_error]
+#[proc_macro_derive(VariantsStruct, attributes(struct_bounds, struct_derive, struct_name, field_name, catch_all, dense_key, multi, auto_insert, jump_table, preserve_case, rename_fields, struct_doc, keep_lints, into_args, serde_skip_maps, serde_pairs, markers, merge_with, table, field_order, cell, cross_eq, random, no_alloc, boxed_fields, impl_attr, ordered_maps, async_locks, impl_for, prefill_keys, handlers, visitor, for_each_macro, getter_table, repr_helpers))]
+pub fn derive_variants_struct(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as ItemEnum);
+    let enum_path = input.ident.clone().into();
+    expand(&input, &enum_path, false).into()
+}
+
+/// The attributes read by this crate, which have to be removed from the enum in the attribute macro.
+const ENUM_ATTRIBUTES: &[&str] = &["struct_bounds", "struct_derive", "struct_name", "catch_all", "auto_insert", "jump_table", "preserve_case", "rename_fields", "struct_doc", "keep_lints", "into_args", "serde_skip_maps", "serde_pairs", "markers", "merge_with", "table", "field_order", "cell", "cross_eq", "random", "no_alloc", "boxed_fields", "impl_attr", "ordered_maps", "async_locks", "impl_for", "handlers", "visitor", "for_each_macro", "getter_table", "repr_helpers"];
+const VARIANT_ATTRIBUTES: &[&str] = &["field_name", "dense_key", "multi", "prefill_keys"];
+
+/// Generates the variants struct and impl like the derive, and also adds constructors for the struct to the enum.
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn variants_struct(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = proc_macro2::TokenStream::from(args);
+    if !args.is_empty() {
+        emit_error!(args, "`variants_struct` doesn't take any arguments");
+    }
+    let mut input = parse_macro_input!(input as ItemEnum);
+    let enum_path = input.ident.clone().into();
+    let generated = expand(&input, &enum_path, true);
+
+    // the enum is emitted again without the attributes, which aren't known to the compiler
+    let is_ours = |attr: &syn::Attribute, names: &[&str]| names.iter().any(|name| attr.path.is_ident(name));
+    input.attrs.retain(|attr| !is_ours(attr, ENUM_ATTRIBUTES));
+    for var in &mut input.variants {
+        var.attrs.retain(|attr| !is_ours(attr, VARIANT_ATTRIBUTES));
+    }
+    (quote! {
+        #input
+
+        #generated
+    }).into()
+}
+
+/// Generates the variants struct and impl for an enum defined somewhere else, from a copy of its definition.
+#[proc_macro_error]
+#[proc_macro]
+pub fn variants_struct_for(input: TokenStream) -> TokenStream {
+    let RemoteEnum { item, path } = parse_macro_input!(input as RemoteEnum);
+    expand(&item, &path, false).into()
+}
+
+/// Derives an enum of the struct's fields, which is the reverse of `VariantsStruct`.
+#[proc_macro_error]
+#[proc_macro_derive(StructVariants, attributes(enum_name, enum_derive, variant_name))]
+pub fn derive_struct_variants(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::ItemStruct);
+    let struct_ident = &input.ident;
+    let mut enum_ident = format_ident!("{}Field", struct_ident);
+    let visibility = &input.vis;
+
+    // read the `enum_name` and `enum_derive` attributes
+    let mut derives: Vec<syn::Path> = ["Clone", "Copy", "PartialEq", "Eq", "Hash", "Debug"].iter()
+        .map(|derive| syn::parse_str(derive).unwrap())
+        .collect();
+    for attr in &input.attrs {
+        if attr.path.is_ident("enum_name") {
+            match attr.parse_meta() {
+                Ok(syn::Meta::NameValue(syn::MetaNameValue {lit, ..})) => {
+                    if let Some(ident) = parse_ident_lit(&lit, false) {
+                        enum_ident = ident;
+                    }
+                }
+                Ok(meta) => emit_error!(meta, "expected a name, like `enum_name = \"...\"`"),
+                Err(e) => emit_error!(e.span(), "{}", e)
+            }
+        } else if attr.path.is_ident("enum_derive") {
+            match attr.parse_meta() {
+                Ok(syn::Meta::List(syn::MetaList {nested, ..})) => {
+                    for meta in nested {
+                        match meta {
+                            syn::NestedMeta::Meta(syn::Meta::Path(path)) => derives.push(path),
+                            _ => emit_error!(meta, "only path arguments are accepted")
+                        }
+                    }
+                }
+                Ok(meta) => emit_error!(meta, "expected a list, like `enum_derive(...)`"),
+                Err(e) => emit_error!(e.span(), "{}", e)
+            }
+        }
+    }
+
+    let named = match &input.fields {
+        Fields::Named(syn::FieldsNamed { named, .. }) => named,
+        _ => {
+            emit_error!(input.fields, "only structs with named fields are allowed");
+            return TokenStream::new();
+        }
+    };
+
+    // every field becomes a variant, named after the field in PascalCase unless `variant_name` is given
+    let mut variants = vec![];
+    let mut field_idents = vec![];
+    for field in named {
+        let field_ident = field.ident.as_ref().unwrap();
+        let mut variant_name = None;
+        for attr in &field.attrs {
+            if attr.path.is_ident("variant_name") {
+                match attr.parse_meta() {
+                    Ok(syn::Meta::NameValue(syn::MetaNameValue {lit, ..})) => {
+                        if variant_name.is_some() {
+                            emit_error!(attr, "`variant_name` can only be given once per field");
+                        } else {
+                            variant_name = parse_ident_lit(&lit, false);
+                        }
+                    }
+                    Ok(meta) => emit_error!(meta, "expected a name, like `variant_name = \"...\"`"),
+                    Err(e) => emit_error!(e.span(), "{}", e)
+                }
+            }
+        }
+        let variant = variant_name.unwrap_or_else(|| {
+            let name = field_ident.unraw().to_string().to_pascal_case();
+            format_ident!("{}", name, span = field_ident.span())
+        });
+        variants.push(variant);
+        field_idents.push(field_ident);
+    }
+
+    // the fields can only be accessed through the enum if they all have the same type
+    let field_ty = named.first().map(|field| &field.ty);
+    let ty_string = |ty: &syn::Type| quote!(#ty).to_string();
+    let same_type = named.iter().all(|field| field_ty.map(ty_string) == Some(ty_string(&field.ty)));
+    let accessors = match field_ty {
+        Some(ty) if same_type => {
+            let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+            quote! {
+                impl #impl_generics #struct_ident #ty_generics #where_clause {
+                    pub fn get(&self, field: #enum_ident) -> &#ty {
+                        match field {
+                            #(#enum_ident::#variants => &self.#field_idents),*
+                        }
+                    }
+
+                    pub fn get_mut(&mut self, field: #enum_ident) -> &mut #ty {
+                        match field {
+                            #(#enum_ident::#variants => &mut self.#field_idents),*
+                        }
+                    }
+                }
+            }
+        }
+        _ => quote! {}
+    };
+
+    (quote! {
+        #[derive(#(#derives),*)]
+        #visibility enum #enum_ident {
+            #(#variants),*
+        }
+
+        #accessors
+    }).into()
+}
+
+/// Inputs that must be rejected with an error, rather than expanding to broken code.
+///
+/// An unsupported variant is reported on its own, without breaking the rest of the struct:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// enum Hello {
+///     World,
+///     There(i32, i32)
+/// }
+/// ```
+///
+/// Every variant must generate a different field:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// enum Hello {
+///     World,
+///     #[field_name = "world"] There
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// enum Hello {
+///     World,
+///     #[field_name(world)] There
+/// }
+/// ```
+///
+/// `cross_eq` can't be combined with a derived `PartialEq`:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// #[struct_derive(PartialEq)]
+/// #[cross_eq]
+/// enum Hello {
+///     World
+/// }
+/// ```
+///
+/// An explicit field order must name every variant exactly once:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// #[field_order(World)]
+/// enum Hello {
+///     World,
+///     There
+/// }
+/// ```
+///
+/// Tables need an enum for their columns:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// #[table(name = "Chart")]
+/// enum Hello {
+///     World
+/// }
+/// ```
+///
+/// `multi` needs a key to store the values under:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// enum Hello {
+///     #[multi] World
+/// }
+/// ```
+///
+/// The keys of a `dense_key` variant must be literals, and can only be given once:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// enum Hello {
+///     #[dense_key(keys("if", "if"))] Keyword(&'static str)
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// const IF: &str = "if";
+///
+/// #[derive(VariantsStruct)]
+/// enum Hello {
+///     #[dense_key(keys(IF))] Keyword(&'static str)
+/// }
+/// ```
+///
+/// `no_alloc` refuses fields that would be stored on the heap:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// #[no_alloc]
+/// enum Hello {
+///     World,
+///     There(u8)
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// #[no_alloc]
+/// enum Hello {
+///     World,
+///     #[dense_key(max = 4)] There(u8)
+/// }
+/// ```
+///
+/// `impl_attr` is put on the generated impl blocks, so the methods are gone if it's a false `cfg`:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// #[impl_attr(cfg(any()))]
+/// enum Hello {
+///     World
+/// }
+///
+/// let _ = HelloStruct::new(1);
+/// ```
+///
+/// A jump table can't be boxed:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// #[jump_table]
+/// #[boxed_fields]
+/// enum Hello {
+///     World
+/// }
+/// ```
+///
+/// Keys of tuple and struct variants must implement `Hash` and `Eq`:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(PartialEq)]
+/// struct NotHashable;
+///
+/// #[derive(VariantsStruct)]
+/// enum Hello {
+///     World(NotHashable)
+/// }
+/// ```
+///
+/// or `Ord`, with `ordered_maps`:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// #[ordered_maps]
+/// enum Hello {
+///     World(f32)
+/// }
+/// ```
+///
+/// `struct_bounds` can't make `T` unsized, or use lifetimes that aren't declared:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// #[struct_bounds(?Sized)]
+/// enum Hello {
+///     World
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// #[struct_bounds(Clone + 'a)]
+/// enum Hello {
+///     World
+/// }
+/// ```
+///
+/// `repr_helpers` needs a primitive representation:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// #[repr_helpers]
+/// enum Hello {
+///     World = 1
+/// }
+/// ```
+///
+/// `field_order(discriminant)` needs the discriminants to be integer literals:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// const WORLD: u8 = 1;
+///
+/// #[derive(VariantsStruct)]
+/// #[repr(u8)]
+/// #[field_order(discriminant)]
+/// enum Hello {
+///     World = WORLD
+/// }
+/// ```
+///
+/// `prefill_keys` needs a variant with a key:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// enum Hello {
+///     #[prefill_keys(1)] World
+/// }
+/// ```
+///
+/// `impl_for` only knows about some extras:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// #[impl_for(f32, extras(average))]
+/// enum Hello {
+///     World
+/// }
+/// ```
+///
+/// Names given to `struct_name` and `field_name` must be valid identifiers:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// #[struct_name = "not an ident"]
+/// enum Hello {
+///     World
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// enum Hello {
+///     #[field_name = 5] World
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// enum Hello {
+///     #[field_name("world")] World
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// enum Hello {
+///     #[field_name(some::path)] World
+/// }
+/// ```
+///
+/// `serde_skip_maps` needs serde to be derived:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// #[serde_skip_maps]
+/// enum Hello {
+///     World,
+///     There(i32)
+/// }
+/// ```
+///
+/// Jump tables need the enum to be `Copy`:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// #[jump_table]
+/// enum Hello {
+///     World,
+///     There
+/// }
+/// ```
+///
+/// Jump tables only work with unit variants:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// #[jump_table]
+/// enum Hello {
+///     World,
+///     There(i32)
+/// }
+/// ```
+///
+/// Field names have to be identifiers:
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// #[rename_fields(prefix = "v-")]
+/// enum Hello {
+///     World
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use variants_struct::VariantsStruct;
+/// #[derive(VariantsStruct)]
+/// #[rename_fields = "kebab-case"]
+/// enum Hello {
+///     World
+/// }
+/// ```
+///
+/// `StructVariants` needs named fields to name the variants after:
+///
+/// ```compile_fail
+/// # use variants_struct::StructVariants;
+/// #[derive(StructVariants)]
+/// struct Pair(i32, i32);
+/// ```
+#[cfg(doctest)]
+mod compile_fail {}
@@ -0,0 +1,71 @@
+//! The code generated by `#[async_locks]`, checked against `tokio`.
+
+use variants_struct::VariantsStruct;
+
+#[derive(VariantsStruct)]
+#[struct_derive(Debug, PartialEq, Default)]
+#[async_locks]
+pub enum Counter {
+    Hits,
+    Misses
+}
+
+#[derive(VariantsStruct, Clone, Copy)]
+#[struct_derive(Debug, PartialEq)]
+#[async_locks(mutex)]
+#[jump_table]
+pub enum Queue {
+    Incoming,
+    Outgoing
+}
+
+#[derive(VariantsStruct)]
+#[struct_derive(Debug, PartialEq)]
+#[async_locks]
+#[boxed_fields]
+pub enum Page {
+    Front,
+    Back
+}
+
+/// `tokio` under another name, like a renamed dependency.
+mod reexport {
+    pub use tokio as rt;
+}
+
+#[derive(VariantsStruct)]
+#[struct_derive(Debug, PartialEq)]
+#[async_locks(mutex, crate = "reexport::rt")]
+pub enum Limit {
+    Connections,
+    Requests
+}
+
+#[tokio::test]
+async fn async_locks() {
+    let counters = CounterStructLocks::new(CounterStruct::new(1, 2));
+    *counters.write(&Counter::Misses).await += 10;
+    assert_eq!(*counters.read(&Counter::Hits).await, 1);
+    let hits = counters.read(&Counter::Hits).await;
+    let misses = counters.read(&Counter::Misses).await;
+    assert_eq!((*hits, *misses), (1, 12));
+    drop((hits, misses));
+    assert_eq!(counters.into_inner(), CounterStruct::new(1, 12));
+    assert_eq!(CounterStructLocks::<u8>::default().into_inner(), CounterStruct::default());
+
+    let queues = QueueStructLocks::from(QueueStruct::new(vec![1], vec![]));
+    let incoming = queues.lock(&Queue::Incoming).await.pop();
+    queues.lock(&Queue::Outgoing).await.extend(incoming);
+    assert_eq!(queues.into_inner().values, [vec![], vec![1]]);
+
+    let pages = PageStructLocks::new(PageStruct::new("front", "back"));
+    *pages.write(&Page::Back).await = "new back";
+    assert_eq!(*pages.into_inner().back, "new back");
+
+    let limits = std::sync::Arc::new(LimitStructLocks::new(LimitStruct::new(1, 2)));
+    let shared = std::sync::Arc::clone(&limits);
+    tokio::spawn(async move {
+        *shared.lock(&Limit::Requests).await += 1;
+    }).await.unwrap();
+    assert_eq!(*limits.lock(&Limit::Requests).await, 3);
+}
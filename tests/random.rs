@@ -0,0 +1,58 @@
+//! The code generated by `#[random]`, checked against `rand` 0.8.
+
+use rand::distributions::{Distribution, Standard};
+use rand::rngs::mock::StepRng;
+use variants_struct::VariantsStruct;
+
+/// `rand` under another name, like a renamed dependency.
+mod reexport {
+    pub use rand as rand08;
+}
+
+#[derive(VariantsStruct)]
+#[random]
+pub enum Color {
+    Red,
+    Green
+}
+
+#[derive(VariantsStruct)]
+#[random]
+#[into_args]
+#[merge_with(Color)]
+pub enum Hello {
+    World,
+    There(i32),
+    #[dense_key(max = 2)] Slot(u8)
+}
+
+#[derive(VariantsStruct, Clone, Copy)]
+#[random]
+#[jump_table]
+pub enum Jump {
+    First,
+    Second
+}
+
+#[derive(VariantsStruct)]
+#[random(crate = "reexport::rand08")]
+pub enum Renamed {
+    Left,
+    Right
+}
+
+#[test]
+fn random() {
+    // counts up from 1, so the order that the values are sampled in can be checked
+    let mut rng = StepRng::new(1, 1);
+    let hello: HelloStruct<u32> = Standard.sample(&mut rng);
+    assert_eq!((hello.world, hello.color.red, hello.color.green), (1, 2, 3));
+    assert!(hello.there.is_empty());
+    assert_eq!(hello.slot, vec![None, None]);
+
+    let jump: JumpStruct<u32> = Standard.sample(&mut rng);
+    assert_eq!(jump.values, [4, 5]);
+
+    let renamed: RenamedStruct<u32> = Standard.sample(&mut rng);
+    assert_eq!((renamed.left, renamed.right), (6, 7));
+}
@@ -1,4 +1,4 @@
-use variants_struct::VariantsStruct;
+use variants_struct::{VariantsStruct, StructVariants, variants_struct_for};
 
 #[derive(VariantsStruct)]
 #[struct_derive(Copy, Clone, Default, PartialEq, Debug)]
@@ -65,6 +65,29 @@ fn default() {
     assert_eq!(hello.there, manual.there);
 }
 
+#[test]
+fn rows_and_columns() {
+    let columns = HelloStruct::new(vec![1, 2, 3], vec![4, 5]);
+    let rows = columns.into_rows();
+    assert_eq!(rows, vec![HelloStruct::new(1, 4), HelloStruct::new(2, 5)]);
+
+    let columns: HelloStruct<Vec<i32>> = rows.into_iter().collect();
+    assert_eq!(columns, HelloStruct::new(vec![1, 2], vec![4, 5]));
+
+    let empty: HelloStruct<Vec<i32>> = std::iter::empty().collect();
+    assert!(empty.into_rows().is_empty());
+}
+
+static STATIC_HELLO: HelloStruct<u8> = HelloStruct::new(1, 2);
+const CONST_EMPTY: EmptyStruct<u8> = EmptyStruct::new();
+
+#[test]
+fn const_new() {
+    assert_eq!(STATIC_HELLO.world, 1);
+    assert_eq!(*STATIC_HELLO.get_unchecked(&Hello::There), 2);
+    assert!(CONST_EMPTY.is_empty());
+}
+
 #[test]
 fn bounds_and_derive() {
     let hello = HelloStruct::new(HasTuples::Zero, HasTuples::One("hello"));
@@ -100,10 +123,247 @@ fn renaming() {
     assert_eq!(*hello.get_unchecked(&NotThisName::NotThis), 1);
 }
 
+#[derive(VariantsStruct)]
+#[allow(dead_code)]
+enum IdentName {
+    Plain,
+    #[field_name(or_this)] NotThat,
+    #[field_name(r#type)] Kind
+}
+
+#[test]
+fn field_name_ident() {
+    let names = IdentNameStruct {
+        plain: 2,
+        or_this: 0,
+        r#type: 1
+    };
+    assert_eq!(*names.get_unchecked(&IdentName::NotThat), 0);
+    assert_eq!(*names.get_unchecked(&IdentName::Kind), 1);
+}
+
+#[derive(VariantsStruct)]
+#[preserve_case]
+#[allow(dead_code)]
+enum Http {
+    HTTPOk,
+    NotFound(u16),
+    #[field_name = "other"] Other
+}
+
+#[test]
+fn preserve_case() {
+    let mut http = HttpStruct::new(200, 0);
+    http.NotFound_insert(0, 404);
+    assert_eq!(http.HTTPOk, 200);
+    assert_eq!(http.NotFound.get(&0), Some(&404));
+    assert_eq!(*http.get_unchecked(&Http::Other), 0);
+}
+
+#[derive(VariantsStruct)]
+#[rename_fields(prefix = "v_", suffix = "_count")]
+#[allow(dead_code)]
+enum Prefixed {
+    Len,
+    Iter(u8),
+    #[field_name = "other"] Other
+}
+
+#[test]
+fn rename_fields() {
+    let mut prefixed = PrefixedStruct::new(1, 2);
+    prefixed.v_iter_count_insert(3, 4);
+    assert_eq!(prefixed.v_len_count, 1);
+    assert_eq!(prefixed.v_iter_count(&3), Some(&4));
+    assert_eq!(prefixed.other, 2);
+}
+
+#[derive(VariantsStruct)]
+#[rename_fields = "camelCase"]
+#[allow(dead_code)]
+enum Camel {
+    FirstValue,
+    SecondValue
+}
+
+#[test]
+fn rename_fields_case() {
+    let camel = CamelStruct::new(1, 2);
+    assert_eq!(camel.firstValue + camel.secondValue, 3);
+}
+
+#[derive(VariantsStruct)]
+#[rename_fields(strip_prefix = "Token")]
+#[allow(dead_code)]
+enum TokenKind {
+    TokenIdent,
+    TokenLiteral,
+    Token,
+    Token2,
+    Eof
+}
+
+#[derive(VariantsStruct)]
+#[rename_fields(strip_prefix)]
+#[allow(dead_code, clippy::enum_variant_names)]
+enum Detected {
+    ItemFn,
+    ItemFnPointer,
+    ItemStruct
+}
+
+#[test]
+fn strip_prefix() {
+    let tokens = TokenKindStruct::new(1, 2, 3, 4, 5);
+    assert_eq!(tokens.ident + tokens.literal, 3);
+    assert_eq!(tokens.token + tokens.token_2 + tokens.eof, 12);
+
+    let detected = DetectedStruct::new(1, 2, 3);
+    assert_eq!(detected.r#fn + detected.fn_pointer + detected.r#struct, 6);
+}
+
+#[derive(VariantsStruct)]
+#[allow(dead_code)]
+enum Shouting {
+    #[field_name(LOUD)] Loud,
+    #[field_name = "Quiet"] Quiet(u8)
+}
+
+#[derive(VariantsStruct)]
+#[keep_lints]
+#[allow(dead_code)]
+enum Linted {
+    Checked
+}
+
+#[test]
+fn lints() {
+    let mut shouting = ShoutingStruct::new(1);
+    shouting.Quiet_insert(2, 3);
+    assert_eq!(shouting.LOUD, 1);
+    assert_eq!(*LintedStruct::new(4).get_unchecked(&Linted::Checked), 4);
+}
+
+#[derive(VariantsStruct)]
+#[struct_doc(hidden)]
+pub enum Plumbing {
+    Pipe,
+    Valve(u8)
+}
+
+#[test]
+fn hidden() {
+    let mut plumbing = PlumbingStruct::new(1);
+    plumbing.valve_insert(2, 3);
+    assert_eq!(plumbing.iter().count(), 2);
+}
+
+#[derive(VariantsStruct)]
+#[into_args]
+#[allow(dead_code)]
+#[getter_table]
+enum Greeting {
+    Hello,
+    Goodbye,
+    Named(&'static str),
+    #[multi] Many(u8),
+    #[dense_key(max = 4)] Numbered(u8)
+}
+
+#[test]
+fn into_args() {
+    let mut greetings: GreetingStruct<String> = GreetingStruct::with_capacity("hello", "goodbye", 2);
+    greetings.named_insert("there", "hello there");
+    greetings.many_insert(1, "hi");
+    greetings.numbered_insert(3, "three");
+    greetings.push(Greeting::Goodbye, "bye");
+    greetings.push(Greeting::Many(1), "hey");
+    assert_eq!(greetings.hello, "hello");
+    assert_eq!(greetings.goodbye, "bye");
+    assert_eq!(greetings.get_all(&Greeting::Many(1)), ["hi", "hey"]);
+    assert_eq!(greetings.get(&Greeting::Numbered(3)).map(String::as_str), Some("three"));
+}
+
+#[derive(VariantsStruct, PartialEq, Eq, Hash)]
+pub enum Cmd {
+    Get(i32),
+    Iter(u8),
+    #[dense_key(max = 2)] Drain(u8)
+}
+
+// a user trait with the name that the visitor would have without `visitor`
+pub trait CmdStructVisitor {}
+
+#[test]
+fn colliding_accessors() {
+    let mut cmds = CmdStruct::new();
+    cmds.get_insert(1, "get");
+    cmds.iter_insert(2, "iter");
+    cmds.drain_insert(1, "drain");
+    assert_eq!(cmds.get(&Cmd::Get(1)), Some(&"get"));
+    assert_eq!(cmds.get_remove(&1), Some("get"));
+    assert_eq!(cmds.iter().count(), 2);
+    assert_eq!(cmds.drain().count(), 2);
+}
+
 // Testing with serde
 
 use serde::{Deserialize, Serialize};
 
+#[derive(VariantsStruct, PartialEq, Eq, Hash)]
+#[struct_derive(Serialize, Deserialize)]
+#[serde_skip_maps]
+#[catch_all = "other"]
+pub enum Cached {
+    Hits,
+    Misses,
+    Entry(String),
+    #[multi] History(u8),
+    #[dense_key(max = 2)] Slot(u8)
+}
+
+#[test]
+fn serde_skip_maps() {
+    let mut start = CachedStruct::new(2, 3);
+    start.entry_insert("key".to_string(), 4);
+    start.history_insert(5, 6);
+    start.slot_insert(1, 7);
+
+    let string = serde_json::to_string(&start).unwrap();
+    assert_eq!(string, r#"{"hits":2,"misses":3,"slot":[null,7]}"#);
+
+    let end: CachedStruct<NoDefault> = serde_json::from_str(r#"{"hits":2,"misses":3,"slot":[null,null]}"#).unwrap();
+    assert!(end.entry.is_empty() && end.history.is_empty() && end.other.is_empty());
+}
+
+#[derive(Deserialize)]
+pub struct NoDefault(pub i32);
+
+#[derive(VariantsStruct, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Debug)]
+#[struct_derive(PartialEq, Debug)]
+#[serde_pairs]
+pub enum Pair {
+    Unit,
+    Keyed(String),
+    #[dense_key(max = 2)] Dense(u8)
+}
+
+#[test]
+fn serde_pairs() {
+    let mut start = PairStruct::new(1);
+    start.keyed_insert("a".to_string(), 2);
+    start.dense_insert(1, 3);
+
+    let string = serde_json::to_string(&start).unwrap();
+    assert_eq!(string, r#"[["Unit",1],[{"Keyed":"a"},2],[{"Dense":1},3]]"#);
+
+    let end: PairStruct<i32> = serde_json::from_str(&string).unwrap();
+    assert_eq!(start, end);
+
+    assert!(serde_json::from_str::<PairStruct<i32>>(r#"[[{"Keyed":"a"},2]]"#).is_err());
+    assert!(serde_json::from_str::<PairStruct<i32>>(r#"[["Unit",1],[{"Dense":2},3]]"#).is_err());
+}
+
 #[derive(VariantsStruct)]
 #[struct_derive(Serialize, Deserialize, PartialEq, Debug)]
 pub enum Asdf {
@@ -121,3 +381,1565 @@ fn test_serde() {
     let end: AsdfStruct<i32> = serde_json::from_str(&string).unwrap();
     assert_eq!(start, end);
 }
+
+#[test]
+fn serialize_borrowed() {
+    let owned = AsdfStruct::new(vec![1], vec![2, 3]);
+    let borrowed: AsdfStruct<&Vec<i32>> = owned.each_ref();
+    assert_eq!(serde_json::to_string(&borrowed).unwrap(), serde_json::to_string(&owned).unwrap());
+
+    let mut pairs = PairStruct::new(String::from("a"));
+    pairs.keyed_insert("b".to_string(), String::from("c"));
+    assert_eq!(serde_json::to_string(&pairs.map_ref(String::as_str)).unwrap(), serde_json::to_string(&pairs).unwrap());
+    assert_eq!(serde_json::to_string(&pairs.each_ref()).unwrap(), serde_json::to_string(&pairs).unwrap());
+}
+
+#[derive(VariantsStruct)]
+#[struct_bounds(Clone)]
+#[allow(dead_code)]
+enum Setting {
+    Volume,
+    Brightness,
+    Preset(u8)
+}
+
+#[test]
+fn new_default() {
+    let settings: SettingStruct<Vec<u8>> = SettingStruct::new_default();
+    assert!(settings.volume.is_empty() && settings.brightness.is_empty());
+    assert!(settings.preset.is_empty());
+}
+
+#[test]
+fn snapshot() {
+    let mut settings: SettingStruct<Vec<u8>> = SettingStruct::new_default();
+    settings.volume.push(1);
+    let first = settings.snapshot();
+    settings.preset_insert(2, vec![3]);
+    let second = settings.snapshot();
+    settings.brightness.push(4);
+
+    settings.restore(second);
+    assert_eq!(settings.preset_mut(&2), Some(&mut vec![3]));
+    assert!(settings.brightness.is_empty());
+    settings.restore(first);
+    assert_eq!(settings.volume, [1]);
+    assert!(settings.preset.is_empty());
+}
+
+#[derive(VariantsStruct, Debug, PartialEq)]
+#[allow(dead_code)]
+enum Signal {
+    Ping(()),
+    Pong(),
+    Marker {},
+    Named { nothing: () },
+    Data(u8)
+}
+
+#[test]
+fn unit_payloads() {
+    let mut signals = SignalStruct::new(1, 2, 3, 4);
+    signals.data_insert(5, 6);
+    assert_eq!(signals.ping + signals.pong + signals.marker + signals.named, 10);
+    assert_eq!(signals[Signal::Named { nothing: () }], 4);
+    signals[Signal::Ping(())] = 7;
+    assert_eq!(signals.iter_unit().next(), Some((Signal::Ping(()), &7)));
+    assert_eq!(signals.len(), 5);
+}
+
+// Panics
+
+thread_local! {
+    static PANIC_FILE: std::cell::RefCell<Option<String>> = const { std::cell::RefCell::new(None) };
+}
+
+#[test]
+fn track_caller() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        PANIC_FILE.with(|file| *file.borrow_mut() = info.location().map(|loc| loc.file().to_string()));
+        previous(info);
+    }));
+
+    let tuple_boi = HasTuplesStruct::new(3);
+    let result = std::panic::catch_unwind(|| *tuple_boi.get_unchecked(&HasTuples::OtherOne(1)));
+    assert!(result.is_err());
+    PANIC_FILE.with(|file| assert_eq!(file.borrow().as_deref(), Some(file!())));
+}
+
+// Empty enums
+
+#[derive(VariantsStruct)]
+#[struct_derive(Default, Clone, PartialEq, Debug)]
+pub enum Empty {}
+
+fn lookup<T: Clone>(table: &EmptyStruct<T>, var: &Empty) -> Option<T> {
+    table.get(var).cloned()
+}
+
+#[test]
+fn empty() {
+    let table: EmptyStruct<String> = EmptyStruct::new();
+    assert_eq!(table, Default::default());
+    let _ = lookup::<String>;
+}
+
+// Catch-all
+
+#[derive(VariantsStruct, PartialEq, Eq, Hash, Debug)]
+#[non_exhaustive]
+#[catch_all = "other"]
+#[getter_table]
+pub enum Growing {
+    Known,
+    Keyed(u8)
+}
+
+#[test]
+fn catch_all() {
+    let mut table = GrowingStruct::new(1);
+    table.keyed.insert(2, 20);
+    table.other.insert(Growing::Known, 100);
+    assert_eq!(table.get(&Growing::Known), Some(&1));
+    assert_eq!(table.get(&Growing::Keyed(2)), Some(&20));
+    assert!(table.other.contains_key(&Growing::Known));
+}
+
+#[test]
+fn is_map_backed() {
+    const _: () = assert!(!GrowingStruct::<i32>::is_map_backed(&Growing::Known));
+    assert!(GrowingStruct::<i32>::is_map_backed(&Growing::Keyed(2)));
+    assert!(!InstructionStruct::<i32>::is_map_backed(&Instruction::Load));
+    assert!(GreetingStruct::<i32>::is_map_backed(&Greeting::Numbered(1)));
+    assert!(GreetingStruct::<i32>::is_map_backed(&Greeting::Many(1)));
+    assert!(!GreetingStruct::<i32>::is_map_backed(&Greeting::Hello));
+}
+
+// Discriminants
+
+#[derive(VariantsStruct, PartialEq, Debug)]
+#[repr(u8)]
+#[repr_helpers]
+#[field_order(discriminant)]
+pub enum Opcode {
+    Load = 7,
+    Store = 3,
+    Jump
+}
+
+// without `repr_helpers`, the enum can have its own `from_repr`
+#[derive(VariantsStruct, PartialEq, Debug)]
+#[repr(u8)]
+pub enum Switch {
+    Off,
+    On
+}
+
+impl Switch {
+    pub fn from_repr(repr: u8) -> Switch {
+        if repr == 0 { Switch::Off } else { Switch::On }
+    }
+}
+
+#[test]
+fn repr() {
+    assert_eq!(Opcode::Load.as_repr(), 7);
+    assert_eq!(Opcode::Jump.as_repr(), 4);
+    assert_eq!(Opcode::from_repr(3), Some(Opcode::Store));
+    assert_eq!(Opcode::from_repr(0), None);
+    assert_eq!(Switch::from_repr(2), Switch::On);
+
+    let opcodes = OpcodeStruct::new(3, 4, 7);
+    assert_eq!((opcodes.store, opcodes.jump, opcodes.load), (3, 4, 7));
+    assert_eq!(opcodes.iter().map(|(var, _)| var).collect::<Vec<_>>(), vec![Opcode::Store, Opcode::Jump, Opcode::Load]);
+}
+
+// Dense keys
+
+#[derive(VariantsStruct, PartialEq, Debug)]
+#[handlers]
+#[for_each_macro]
+pub enum Port {
+    Console,
+    #[dense_key(max = 64)] Serial(u8),
+    Network { port: u16 }
+}
+
+#[test]
+fn dense_keys() {
+    let mut ports = PortStruct::new("tty");
+    assert_eq!(ports.serial.len(), 64);
+    ports.serial[3] = Some("com3");
+    ports.network.insert(8080, "http");
+    assert_eq!(ports.get(&Port::Serial(3)), Some(&"com3"));
+    assert_eq!(ports.get(&Port::Serial(4)), None);
+    assert_eq!(ports.get(&Port::Serial(200)), None);
+    *ports.get_mut_unchecked(&Port::Serial(3)) = "ttyS3";
+    assert_eq!(*ports.get_unchecked(&Port::Serial(3)), "ttyS3");
+    assert_eq!(ports.get(&Port::Network { port: 8080 }), Some(&"http"));
+}
+
+#[derive(VariantsStruct, PartialEq, Debug)]
+pub enum Keyword {
+    Other,
+    #[dense_key(keys("fn", "let", "match"))] Reserved(&'static str),
+    #[dense_key(keys(1, 2, 4))] Flag { bit: u8 }
+}
+
+#[test]
+fn static_keys() {
+    let mut keywords = KeywordStruct::new(0);
+    assert_eq!(keywords.reserved, [None; 3]);
+    keywords.push(Keyword::Reserved("let"), 1);
+    keywords.flag_insert(4, 2);
+    assert_eq!(keywords.get(&Keyword::Reserved("let")), Some(&1));
+    assert_eq!(keywords.get(&Keyword::Reserved("const")), None);
+    assert_eq!(keywords.reserved(&"let"), Some(&1));
+    assert_eq!(keywords.get(&Keyword::Flag { bit: 4 }), Some(&2));
+    assert_eq!(keywords.flag_remove(&3), None);
+    assert_eq!(keywords.get_key_value(&Keyword::Flag { bit: 4 }), Some((Keyword::Flag { bit: 4 }, &2)));
+
+    let mut seen: Vec<_> = keywords.iter().map(|(var, value)| (var, *value)).collect();
+    seen.sort_by_key(|(_, value)| *value);
+    assert_eq!(seen, vec![(Keyword::Other, 0), (Keyword::Reserved("let"), 1), (Keyword::Flag { bit: 4 }, 2)]);
+}
+
+#[test]
+#[should_panic(expected = "dense key is not one of the keys of the variant")]
+fn static_keys_missing() {
+    let mut keywords = KeywordStruct::new(0);
+    keywords.reserved_insert("const", 1);
+}
+
+#[derive(VariantsStruct, Clone, Copy, PartialEq, Debug)]
+#[struct_derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[no_alloc]
+pub enum Register {
+    Pc,
+    #[dense_key(max = 4, array)] General(u8),
+    #[dense_key(keys("lo", "hi"))] Half(&'static str)
+}
+
+#[test]
+fn no_alloc() {
+    let mut registers = RegisterStruct::new(1);
+    registers.general_insert(2, 3);
+    registers.push(Register::Half("hi"), 4);
+    assert_eq!(registers.general, [None, None, Some(3), None]);
+    assert_eq!(registers.get(&Register::Half("hi")), Some(&4));
+    assert_eq!(registers.get(&Register::General(9)), None);
+
+    let doubled = registers.map_ref(|value| value * 2);
+    assert_eq!(doubled.half, [None, Some(8)]);
+    let checked: RegisterStruct<Option<i32>> = registers.map_ref(|value| Some(*value));
+    assert_eq!(checked.transpose(), Some(registers.clone()));
+
+    let json = serde_json::to_string(&registers).unwrap();
+    assert_eq!(json, r#"{"pc":1,"general":[null,null,3,null],"half":[null,4]}"#);
+    assert_eq!(serde_json::from_str::<RegisterStruct<i32>>(&json).unwrap(), registers);
+
+    assert_eq!(registers.clone().into_values().collect::<Vec<_>>(), vec![1, 3, 4]);
+    assert_eq!(registers.drain().collect::<Vec<_>>(), vec![(Register::General(2), 3), (Register::Half("hi"), 4)]);
+    assert!(registers.general.iter().all(Option::is_none));
+}
+
+#[test]
+fn with_capacity() {
+    let tuple_boi: HasTuplesStruct<i32> = HasTuplesStruct::with_capacity(3, 100);
+    assert_eq!(tuple_boi.zero, 3);
+    assert!(tuple_boi.one.capacity() >= 100);
+    assert!(tuple_boi.struct_variant.capacity() >= 100);
+
+    let empty: EmptyStruct<i32> = EmptyStruct::with_capacity(10);
+    assert_eq!(empty, EmptyStruct::new());
+}
+
+#[test]
+fn reserve_and_shrink() {
+    let mut tuple_boi: HasTuplesStruct<i32> = HasTuplesStruct::new(3);
+    tuple_boi.reserve(50);
+    assert!(tuple_boi.one.capacity() >= 50);
+    assert!(tuple_boi.other_one.capacity() >= 50);
+    tuple_boi.other_one.insert(1, 1);
+    tuple_boi.shrink_to_fit();
+    assert!(tuple_boi.one.capacity() < 50);
+    assert!(tuple_boi.other_one.capacity() >= 1);
+
+    let mut hello = HelloStruct::new(1, 2);
+    hello.reserve(10);
+    hello.shrink_to_fit();
+}
+
+#[test]
+fn clear_and_reset() {
+    let mut ports = PortStruct::new(5);
+    ports.serial[1] = Some(6);
+    ports.network.insert(80, 7);
+    let capacity = ports.network.capacity();
+
+    ports.clear();
+    assert_eq!(ports.console, 5);
+    assert_eq!(ports.get(&Port::Serial(1)), None);
+    assert!(ports.network.is_empty());
+    assert_eq!(ports.network.capacity(), capacity);
+
+    ports.network.insert(80, 7);
+    ports.reset();
+    assert_eq!(ports.console, 0);
+    assert!(ports.network.is_empty());
+}
+
+#[test]
+fn len_and_is_empty() {
+    let mut ports = PortStruct::new(5);
+    assert_eq!(ports.len(), 1);
+    assert!(ports.is_empty());
+
+    ports.serial[1] = Some(6);
+    ports.network.insert(80, 7);
+    ports.network.insert(443, 8);
+    assert_eq!(ports.len(), 4);
+    assert!(!ports.is_empty());
+
+    assert_eq!(HelloStruct::new(1, 2).len(), 2);
+    assert!(EmptyStruct::<i32>::new().is_empty());
+}
+
+#[test]
+fn to_vec() {
+    let mut tuple_boi = HasTuplesStruct::new(0);
+    tuple_boi.struct_variant.insert(5, 50);
+    tuple_boi.one.insert("one", 1);
+    assert_eq!(tuple_boi.to_vec(), vec![
+        (HasTuples::Zero, 0),
+        (HasTuples::One("one"), 1),
+        (HasTuples::StructVariant { my_field: 5 }, 50)
+    ]);
+
+    let mut ports = PortStruct::new("console");
+    ports.serial[2] = Some("serial");
+    let ports: Vec<_> = ports.to_vec().into_iter().map(|(_, value)| value).collect();
+    assert_eq!(ports, vec!["console", "serial"]);
+
+    assert!(EmptyStruct::<i32>::new().to_vec().is_empty());
+}
+
+#[test]
+fn into_values() {
+    let mut ports = PortStruct::new(vec![1]);
+    ports.serial[2] = Some(vec![2, 2]);
+    ports.network.insert(80, vec![3]);
+    let mut values: Vec<_> = ports.into_values().collect();
+    values.sort();
+    assert_eq!(values, vec![vec![1], vec![2, 2], vec![3]]);
+}
+
+// Iteration
+
+#[derive(PartialEq, Eq, Hash)]
+pub struct NotCloneKey(u8);
+
+#[derive(VariantsStruct)]
+pub enum HasNotCloneKey {
+    Unit,
+    Key(NotCloneKey)
+}
+
+#[test]
+fn into_iterator() {
+    let mut tuple_boi = HasTuplesStruct::new(0);
+    tuple_boi.one.insert("one", 1);
+    tuple_boi.struct_variant.insert(2, 2);
+
+    let mut seen = vec![];
+    for (var, value) in &tuple_boi {
+        seen.push((var, *value));
+    }
+    assert_eq!(seen, vec![
+        (HasTuples::Zero, 0),
+        (HasTuples::One("one"), 1),
+        (HasTuples::StructVariant { my_field: 2 }, 2)
+    ]);
+
+    for (_, value) in &mut tuple_boi {
+        *value += 10;
+    }
+    assert_eq!(tuple_boi.zero, 10);
+    assert_eq!(tuple_boi.struct_variant[&2], 12);
+
+    let mut ports = PortStruct::new(1);
+    ports.serial[9] = Some(2);
+    let values: Vec<_> = ports.iter().map(|(_, value)| *value).collect();
+    assert_eq!(values, vec![1, 2]);
+    assert!(matches!(ports.iter().nth(1), Some((Port::Serial(9), _))));
+
+    // keys that aren't `Clone` don't stop the rest of the struct from working
+    let table = HasNotCloneKeyStruct::new(3);
+    assert_eq!(table.get(&HasNotCloneKey::Unit), Some(&3));
+    assert_eq!(table.get(&HasNotCloneKey::Key(NotCloneKey(0))), None);
+}
+
+#[test]
+fn invert() {
+    let mut ports = PortStruct::new("console");
+    ports.serial[1] = Some("serial");
+    ports.serial[2] = Some("console");
+    ports.network.insert(80, "serial");
+
+    let mut inverted = ports.invert();
+    assert_eq!(inverted.len(), 2);
+    assert_eq!(inverted.remove(&"console").unwrap(), vec![Port::Console, Port::Serial(2)]);
+    assert_eq!(inverted.remove(&"serial").unwrap(), vec![Port::Serial(1), Port::Network { port: 80 }]);
+}
+
+#[test]
+fn map_ref() {
+    let mut ports = PortStruct::new(1);
+    ports.serial[3] = Some(2);
+    ports.network.insert(80, 3);
+    let doubled = ports.map_ref(|value| value * 2);
+    assert_eq!(doubled.console, 2);
+    assert_eq!(doubled.serial(&3), Some(&4));
+    assert_eq!(doubled.len(), 3);
+    assert_eq!(doubled.network(&80), Some(&6));
+
+    let mut observations = ObservationStruct::new(0);
+    observations.push(Observation::Sensor("a"), 1);
+    observations.push(Observation::Sensor("a"), 2);
+    assert_eq!(observations.map_ref(|value| value + 1).sensor(&"a"), &[2, 3]);
+
+    let empty: EmptyStruct<i32> = EmptyStruct::new();
+    let _: EmptyStruct<String> = empty.map_ref(|value| value.to_string());
+}
+
+#[test]
+fn transpose() {
+    let mut ports = PortStruct::new(Some(1));
+    ports.serial[3] = Some(Some(2));
+    ports.network.insert(80, Some(3));
+    let ports = ports.transpose().unwrap();
+    assert_eq!(ports.console, 1);
+    assert_eq!(ports.serial(&3), Some(&2));
+    assert_eq!(ports.serial(&4), None);
+    assert_eq!(ports.network(&80), Some(&3));
+
+    let mut ports = PortStruct::new(Ok(1));
+    ports.serial[3] = Some(Err("bad serial"));
+    assert_eq!(ports.transpose().err(), Some("bad serial"));
+
+    let mut observations = ObservationStruct::new(Some(0));
+    observations.push(Observation::Sensor("a"), Some(1));
+    observations.push(Observation::Sensor("a"), None);
+    assert!(observations.transpose().is_none());
+
+    let empty: EmptyStruct<Result<i32, ()>> = EmptyStruct::new();
+    assert!(empty.transpose().is_ok());
+}
+
+#[derive(VariantsStruct)]
+pub enum Slots {
+    Fixed,
+    #[dense_key(max = 10)] Slot(usize)
+}
+
+#[test]
+fn iterator_traits() {
+    fn fused<I: std::iter::FusedIterator>(_: &I) {}
+
+    let mut hello = HelloStruct::new(1, 2);
+    assert_eq!(hello.iter().len(), 2);
+    let reversed: Vec<_> = hello.iter().rev().map(|(_, value)| *value).collect();
+    assert_eq!(reversed, vec![2, 1]);
+    let zipped: Vec<_> = hello.iter().zip(hello.iter().rev()).map(|((_, a), (_, b))| a + b).collect();
+    assert_eq!(zipped, vec![3, 3]);
+    assert!(matches!(hello.iter_mut().next_back(), Some((Hello::There, _))));
+    assert_eq!(hello.into_values().rev().collect::<Vec<_>>(), vec![2, 1]);
+
+    let mut tuple_boi = HasTuplesStruct::new(0);
+    tuple_boi.one.insert("one", 1);
+    let mut iter = tuple_boi.iter();
+    assert_eq!(iter.len(), 2);
+    iter.next();
+    assert_eq!(iter.len(), 1);
+    fused(&iter);
+
+    let mut slots = SlotsStruct::new(0);
+    slots.slot[5] = Some(5);
+    slots.slot[7] = Some(7);
+    assert_eq!(slots.iter().size_hint(), (1, Some(11)));
+    let reversed: Vec<_> = slots.iter().rev().map(|(_, value)| *value).collect();
+    assert_eq!(reversed, vec![7, 5, 0]);
+}
+
+#[test]
+fn split_iteration() {
+    let mut tuple_boi = HasTuplesStruct::new(0);
+    tuple_boi.one.insert("one", 1);
+    tuple_boi.other_one.insert(2, 2);
+
+    let units: Vec<_> = tuple_boi.iter_unit().collect();
+    assert_eq!(units, vec![(HasTuples::Zero, &0)]);
+    let mut entries: Vec<_> = tuple_boi.iter_map().map(|(_, value)| *value).collect();
+    entries.sort();
+    assert_eq!(entries, vec![1, 2]);
+    assert_eq!(tuple_boi.iter_map().len(), 2);
+
+    for (_, value) in tuple_boi.iter_map_mut() {
+        *value *= 10;
+    }
+    for (_, value) in tuple_boi.iter_unit_mut() {
+        *value = 5;
+    }
+    assert_eq!(tuple_boi.zero, 5);
+    assert_eq!(tuple_boi.one["one"], 10);
+}
+
+#[test]
+fn field_accessors() {
+    let mut tuple_boi = HasTuplesStruct::new(0);
+    assert_eq!(tuple_boi.one_insert("one", 1), None);
+    assert_eq!(tuple_boi.one_insert("one", 2), Some(1));
+    assert_eq!(tuple_boi.one(&"one"), Some(&2));
+    *tuple_boi.one_mut(&"one").unwrap() += 1;
+    assert_eq!(tuple_boi.get(&HasTuples::One("one")), Some(&3));
+    assert_eq!(tuple_boi.one_remove(&"one"), Some(3));
+    assert_eq!(tuple_boi.one(&"one"), None);
+
+    let mut ports = PortStruct::new(0);
+    assert_eq!(ports.serial_insert(3, 30), None);
+    assert_eq!(ports.serial(&3), Some(&30));
+    assert_eq!(ports.serial(&100), None);
+    assert_eq!(ports.serial_remove(&3), Some(30));
+    assert_eq!(ports.serial(&3), None);
+}
+
+#[test]
+fn fallbacks() {
+    let mut tuple_boi = HasTuplesStruct::new(0);
+    tuple_boi.one.insert("fallback", 1);
+    let chain = [HasTuples::One("missing"), HasTuples::One("fallback"), HasTuples::Zero];
+    assert_eq!(tuple_boi.get_with_fallback(&chain), Some(&1));
+    assert_eq!(tuple_boi.get_with_fallback(&chain[2..]), Some(&0));
+    assert_eq!(tuple_boi.get_with_fallback(&[HasTuples::OtherOne(3)]), None);
+    assert_eq!(tuple_boi.get_with_fallback(&[]), None);
+    assert_eq!(*tuple_boi.get_or_else(&HasTuples::OtherOne(3), || &7), 7);
+    assert_eq!(*tuple_boi.get_or_else(&HasTuples::Zero, || &7), 0);
+}
+
+#[test]
+fn gather() {
+    let mut tuple_boi = HasTuplesStruct::new(0);
+    tuple_boi.one.insert("found", 1);
+    let found = tuple_boi.gather([HasTuples::One("found"), HasTuples::OtherOne(3), HasTuples::Zero]);
+    assert_eq!(found, vec![Some(&1), None, Some(&0)]);
+    assert!(tuple_boi.gather(None).is_empty());
+}
+
+#[test]
+fn validate_all() {
+    let mut greetings = GreetingStruct::new(1, -2);
+    greetings.named.insert("ann", -3);
+    greetings.numbered[2] = Some(4);
+    greetings.push(Greeting::Many(1), -5);
+    greetings.push(Greeting::Many(1), 6);
+    let mut seen = vec![];
+    let result = greetings.validate_all(|var, value| {
+        seen.push(*value);
+        match (var, *value < 0) {
+            (Greeting::Many(key), true) => Err(format!("many {}", key)),
+            (_, true) => Err(String::from("negative")),
+            _ => Ok(())
+        }
+    });
+    seen.sort_unstable();
+    assert_eq!(seen, vec![-5, -3, -2, 1, 4, 6]);
+    assert_eq!(result, Err(vec![
+        (String::from("goodbye"), String::from("negative")),
+        (String::from("named"), String::from("negative")),
+        (String::from("many"), String::from("many 1"))
+    ]));
+    let unchecked: GreetingStruct<i32> = GreetingStruct::new(1, 2);
+    assert_eq!(unchecked.validate_all(|_, _| Err(())).map_err(|errors| errors.len()), Err(2));
+}
+
+#[test]
+fn apply_defaults() {
+    let mut defaults = GreetingStruct::new(Some(1), Some(2));
+    defaults.named.insert("ann", Some(3));
+    defaults.named.insert("bob", Some(4));
+    defaults.many.insert(1, vec![Some(5)]);
+    defaults.numbered[0] = Some(Some(6));
+    defaults.numbered[1] = Some(Some(7));
+
+    let mut layered: GreetingStruct<Option<u32>> = GreetingStruct::new(None, Some(8));
+    layered.named.insert("ann", None);
+    layered.numbered[1] = Some(None);
+    let mut plain: GreetingStruct<Option<u32>> = GreetingStruct::new(None, None);
+    plain.named.insert("ann", None);
+    plain.apply_defaults(GreetingStruct::new(Some(1), Some(2)));
+    assert_eq!((plain.hello, plain.goodbye), (None, None));
+
+    layered.apply_defaults_where(defaults, Option::is_none);
+    assert_eq!((layered.hello, layered.goodbye), (Some(1), Some(8)));
+    assert_eq!(layered.named["ann"], Some(3));
+    assert_eq!(layered.named["bob"], Some(4));
+    assert_eq!(layered.many[&1], vec![Some(5)]);
+    assert_eq!(layered.numbered, vec![Some(Some(6)), Some(Some(7)), None, None]);
+
+    let mut cycles = InstructionStruct::new(0, 3, 0, 2);
+    cycles.apply_defaults_where(InstructionStruct::new(1, 1, 1, 1), |value| *value == 0);
+    assert_eq!(cycles.values, [1, 3, 1, 2]);
+}
+
+#[derive(VariantsStruct)]
+pub enum Named {
+    Anonymous,
+    Person(String)
+}
+
+#[test]
+fn borrowed_keys() {
+    let mut named = NamedStruct::new(0);
+    named.person_insert("alice".to_string(), 1);
+    assert_eq!(named.person("alice"), Some(&1));
+    *named.person_mut("alice").unwrap() += 1;
+    assert_eq!(named.person_remove("alice"), Some(2));
+    assert_eq!(named.person("alice"), None);
+}
+
+// Multiple values
+
+#[derive(VariantsStruct, PartialEq, Debug)]
+pub enum Observation {
+    Total,
+    #[multi] Sensor(&'static str)
+}
+
+#[test]
+fn multi() {
+    let mut observations = ObservationStruct::new(0);
+    observations.push(Observation::Sensor("a"), 1);
+    observations.push(Observation::Sensor("a"), 2);
+    observations.sensor_insert("b", 3);
+    observations.push(Observation::Total, 6);
+
+    assert_eq!(observations.get_all(&Observation::Sensor("a")), &[1, 2]);
+    assert_eq!(observations.sensor(&"b"), &[3]);
+    assert_eq!(observations.get_all(&Observation::Sensor("c")), &[] as &[i32]);
+    assert_eq!(observations.get_all(&Observation::Total), &[6]);
+    assert_eq!(observations.get(&Observation::Sensor("a")), Some(&1));
+    *observations.get_mut_unchecked(&Observation::Sensor("a")) = 10;
+    assert_eq!(observations.len(), 4);
+
+    let mut seen: Vec<_> = observations.iter().map(|(var, value)| (var, *value)).collect();
+    seen.sort_by_key(|(_, value)| *value);
+    assert_eq!(seen, vec![
+        (Observation::Sensor("a"), 2),
+        (Observation::Sensor("b"), 3),
+        (Observation::Total, 6),
+        (Observation::Sensor("a"), 10)
+    ]);
+    assert_eq!(observations.to_vec().len(), 4);
+}
+
+#[test]
+fn drain() {
+    let mut tuple_boi = HasTuplesStruct::new(3);
+    tuple_boi.one.insert("hello there", 2);
+    tuple_boi.struct_variant.insert(8, 80);
+    let mut drained: Vec<_> = tuple_boi.drain().collect();
+    drained.sort_by_key(|(_, value)| *value);
+    assert_eq!(drained, vec![(HasTuples::One("hello there"), 2), (HasTuples::StructVariant { my_field: 8 }, 80)]);
+    assert_eq!(tuple_boi.zero, 3);
+    assert!(tuple_boi.is_empty());
+
+    let mut observations = ObservationStruct::new(0);
+    observations.push(Observation::Sensor("a"), 1);
+    observations.push(Observation::Sensor("a"), 2);
+    assert_eq!(observations.drain().count(), 2);
+    assert_eq!(observations.len(), 1);
+
+    let mut ports = PortStruct::new("tty");
+    ports.serial[3] = Some("com3");
+    ports.network.insert(8080, "http");
+    drop(ports.drain());
+    assert!(ports.is_empty());
+    assert_eq!(ports.serial.len(), 64);
+}
+
+#[test]
+fn get_key_value() {
+    let mut tuple_boi = HasTuplesStruct::new(3);
+    tuple_boi.one.insert("hello there", 2);
+    assert_eq!(tuple_boi.get_key_value(&HasTuples::Zero), Some((HasTuples::Zero, &3)));
+    assert_eq!(tuple_boi.get_key_value(&HasTuples::One("hello there")), Some((HasTuples::One("hello there"), &2)));
+    assert_eq!(tuple_boi.get_key_value(&HasTuples::One("asdf")), None);
+    assert_eq!(tuple_boi.one_key_value("hello there"), Some((&"hello there", &2)));
+
+    let mut observations = ObservationStruct::new(0);
+    observations.push(Observation::Sensor("a"), 1);
+    observations.push(Observation::Sensor("a"), 2);
+    assert_eq!(observations.get_key_value(&Observation::Sensor("a")), Some((Observation::Sensor("a"), &1)));
+    assert_eq!(observations.sensor_key_value("a"), Some((&"a", &[1, 2][..])));
+
+    let mut ports = PortStruct::new("tty");
+    ports.serial[3] = Some("com3");
+    assert_eq!(ports.get_key_value(&Port::Serial(3)), Some((Port::Serial(3), &"com3")));
+    assert_eq!(ports.get_key_value(&Port::Serial(4)), None);
+}
+
+// Indexing
+
+#[derive(VariantsStruct, Clone, PartialEq, Eq, Hash)]
+#[auto_insert]
+pub enum Counter {
+    Total,
+    Click(u32),
+    #[multi] Hover(u32),
+    #[dense_key(max = 4)] Key(u8)
+}
+
+#[test]
+fn index() {
+    let mut tuple_boi = HasTuplesStruct::new(0);
+    tuple_boi.one.insert("one", 1);
+    tuple_boi[HasTuples::Zero] = 5;
+    tuple_boi[&HasTuples::One("one")] += 1;
+    assert_eq!(tuple_boi[&HasTuples::Zero], 5);
+    assert_eq!(tuple_boi[HasTuples::One("one")], 2);
+}
+
+#[test]
+fn auto_insert() {
+    let mut counts = CounterStruct::new(0);
+    for _ in 0..3 {
+        counts[Counter::Total] += 1;
+        counts[Counter::Click(7)] += 1;
+        counts[Counter::Hover(7)] += 1;
+        counts[Counter::Key(2)] += 1;
+    }
+    assert_eq!(counts.total, 3);
+    assert_eq!(counts.click(&7), Some(&3));
+    assert_eq!(counts.hover(&7), &[3]);
+    assert_eq!(counts.key(&2), Some(&3));
+    assert_eq!(counts.len(), 4);
+}
+
+// Remote enums
+
+variants_struct_for! {
+    #[struct_name = "AddrTable"]
+    pub enum std::net::IpAddr {
+        V4(std::net::Ipv4Addr),
+        V6(std::net::Ipv6Addr)
+    }
+}
+
+#[test]
+fn remote() {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    let mut table = AddrTable::new();
+    table.push(IpAddr::V4(Ipv4Addr::LOCALHOST), "local");
+    table.v6_insert(Ipv6Addr::LOCALHOST, "also local");
+    assert_eq!(table.get(&IpAddr::V4(Ipv4Addr::LOCALHOST)), Some(&"local"));
+    assert_eq!(table[IpAddr::V6(Ipv6Addr::LOCALHOST)], "also local");
+    assert_eq!(table.iter().count(), 2);
+}
+
+// Attribute macro
+
+#[variants_struct::variants_struct]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[struct_derive(Debug, PartialEq)]
+#[auto_insert]
+pub enum Augmented {
+    First,
+    #[field_name = "extra"] Second,
+    #[multi] Third(u8)
+}
+
+#[test]
+fn attribute_macro() {
+    let mut table: AugmentedStruct<u32> = Augmented::table_default();
+    assert_eq!(table, AugmentedStruct::new(0, 0));
+    assert_eq!(Augmented::table(1, 2), AugmentedStruct::new(1, 2));
+    table[Augmented::Third(3)] += 4;
+    assert_eq!(table.extra, 0);
+    assert_eq!(table.get_all(&Augmented::Third(3)), &[4]);
+}
+
+// Struct variants
+
+#[derive(StructVariants)]
+#[enum_name = "Side"]
+#[enum_derive(VariantsStruct)]
+pub struct Margins<T> {
+    top: T,
+    #[variant_name = "Under"] bottom: T,
+    r#left: T
+}
+
+#[derive(StructVariants)]
+pub struct Mixed {
+    pub name: String,
+    pub count: usize
+}
+
+#[test]
+fn struct_variants() {
+    let mut margins = Margins { top: 1, bottom: 2, left: 3 };
+    *margins.get_mut(Side::Under) += 10;
+    assert_eq!(*margins.get(Side::Top), 1);
+    assert_eq!(*margins.get(Side::Under), 12);
+    assert_eq!(*margins.get(Side::Left), 3);
+
+    // the generated enum can have its own variants struct
+    let names = SideStruct::new("top", "under", "left");
+    assert_eq!(names.get_unchecked(&Side::Under), &"under");
+
+    assert_ne!(MixedField::Name, MixedField::Count);
+}
+
+// Tables
+
+#[derive(VariantsStruct, Clone, Copy)]
+#[table(cols = "Direction", cols_struct = "Directions")]
+pub enum State {
+    Idle,
+    Moving(u8)
+}
+
+#[derive(VariantsStruct, Clone, Copy)]
+#[struct_name = "Directions"]
+pub enum Direction {
+    Left,
+    Right
+}
+
+#[test]
+fn tables() {
+    let mut rows = StateStruct::new(Directions::new("idle left", "idle right"));
+    rows.moving.insert(1, Directions::new("slow left", "slow right"));
+    let mut table = StateDirectionTable::new(rows);
+
+    assert_eq!(table.get(&State::Moving(1), &Direction::Right), Some(&"slow right"));
+    assert_eq!(table.get(&State::Moving(2), &Direction::Right), None);
+    table[(State::Idle, Direction::Left)] = "waiting";
+    assert_eq!(*table.get_unchecked(&State::Idle, &Direction::Left), "waiting");
+}
+
+// Merging
+
+#[derive(VariantsStruct)]
+#[merge_with(Direction = "Directions", Port)]
+pub enum Merged {
+    Own
+}
+
+#[test]
+fn merge_with() {
+    let mut merged = MergedStruct::new(1, Directions::new(2, 3), PortStruct::new(4));
+    assert_eq!(merged.get_direction(&Direction::Right), Some(&3));
+    *merged.get_port_mut(&Port::Console).unwrap() += 1;
+    merged.port.network.insert(80, 6);
+    assert_eq!(merged.get_port(&Port::Network { port: 80 }), Some(&6));
+    assert_eq!(merged.get_port(&Port::Console), Some(&5));
+    assert_eq!(merged.get(&Merged::Own), Some(&1));
+
+    let doubled = merged.map_ref(|value| value * 2);
+    assert_eq!(doubled.direction.left, 4);
+
+    let fresh: MergedStruct<i32> = MergedStruct::new_default();
+    assert_eq!(fresh.direction.right + fresh.port.console, 0);
+    merged.reset();
+    assert_eq!(merged.direction.left, 0);
+    assert!(merged.port.network.is_empty());
+}
+
+// Handlers
+
+#[test]
+fn handlers() {
+    let mut handlers: PortStructHandlers<&str, String> = PortStructHandlers::new(Box::new(|msg| format!("console: {}", msg)));
+    handlers.serial_insert(1, Box::new(|msg| format!("serial: {}", msg)));
+    handlers.network.insert(80, Box::new(|msg| format!("http: {}", msg)));
+
+    assert_eq!(handlers.call(&Port::Console, "hi"), "console: hi");
+    assert_eq!(handlers.call(&Port::Serial(1), "hi"), "serial: hi");
+    assert_eq!(handlers.try_call(&Port::Network { port: 80 }, "hi").unwrap(), "http: hi");
+    assert_eq!(handlers.try_call(&Port::Network { port: 81 }, "hi"), None);
+
+    let handlers = std::sync::Arc::new(handlers);
+    let shared = std::sync::Arc::clone(&handlers);
+    let reply = std::thread::spawn(move || shared.call(&Port::Serial(1), "there")).join().unwrap();
+    assert_eq!(reply, "serial: there");
+}
+
+#[derive(VariantsStruct)]
+#[handlers = "Dispatch"]
+#[struct_name = "Commands"]
+pub enum Command {
+    Stop
+}
+
+// a user type with the name that the handlers would have without `handlers`
+#[allow(dead_code)]
+pub struct CommandHandlers;
+
+#[test]
+fn named_handlers() {
+    let handlers: Dispatch<(), u8> = Commands::new(Box::new(|()| 1));
+    assert_eq!(handlers.call(&Command::Stop, ()), 1);
+}
+
+// Compile-time iteration
+
+macro_rules! name_variant {
+    ($variant:ident, $field:ident, $names:ident) => {
+        $names.push_str(concat!(stringify!($variant), ":", stringify!($field), " "));
+    };
+}
+
+#[derive(VariantsStruct)]
+#[for_each_macro = "for_each_renamed"]
+pub enum Renamed {
+    Only
+}
+
+// `PORT` has the same snake-case name as `Port`, which is fine without `for_each_macro`
+#[derive(VariantsStruct)]
+#[allow(clippy::upper_case_acronyms, dead_code)]
+enum PORT {
+    Other
+}
+
+#[test]
+fn for_each_variant() {
+    let mut names = String::new();
+    for_each_port_variant!(name_variant, names);
+    assert_eq!(names, "Console:console Serial:serial Network:network ");
+    let mut names = String::new();
+    for_each_renamed!(name_variant, names);
+    assert_eq!(names, "Only:only ");
+}
+
+// Marker types
+
+#[derive(VariantsStruct)]
+#[markers = "pin"]
+#[struct_bounds(Clone)]
+pub enum Pin {
+    Ground,
+    #[dense_key(max = 8)] Digital(u8),
+    #[multi] Analog(u8)
+}
+
+#[test]
+fn markers() {
+    let mut pins = PinStruct::new(0.0);
+    assert_eq!(pins.set_typed::<pin::Ground>(1.5), 0.0);
+    pins.get_typed_mut::<pin::Digital>()[2] = Some(3.3);
+    pins.get_typed_mut::<pin::Analog>().insert(1, vec![0.5, 0.7]);
+
+    assert_eq!(*pins.get_typed::<pin::Ground>(), 1.5);
+    assert_eq!(pins.get(&Pin::Digital(2)), Some(&3.3));
+    assert_eq!(pins.get_all(&Pin::Analog(1)), &[0.5, 0.7]);
+}
+
+// Jump tables
+
+#[derive(VariantsStruct, Clone, Copy)]
+#[jump_table]
+#[struct_derive(Debug, PartialEq)]
+#[getter_table]
+pub enum Instruction {
+    Nop,
+    Load,
+    Store,
+    Jump
+}
+
+#[test]
+fn jump_table() {
+    let mut cycles = InstructionStruct::new(1, 3, 3, 2);
+    assert_eq!(cycles[Instruction::Load], 3);
+    cycles[Instruction::Jump] += 1;
+    cycles.push(Instruction::Nop, 0);
+    assert_eq!(cycles.values, [0, 3, 3, 3]);
+    assert_eq!(cycles.get_all(&Instruction::Store), &[3]);
+
+    for (_, value) in cycles.iter_mut() {
+        *value *= 2;
+    }
+    assert_eq!(cycles.iter().map(|(_, value)| *value).sum::<i32>(), 18);
+    assert_eq!(cycles.map_ref(|value| value + 1).values, [1, 7, 7, 7]);
+    assert_eq!(cycles.to_vec().len(), 4);
+}
+
+#[derive(VariantsStruct, Clone, Copy, PartialEq, Debug)]
+#[jump_table]
+#[repr(u16)]
+pub enum Sparse {
+    Low = 10,
+    Lowest = 3,
+    High = 300,
+    Higher
+}
+
+#[derive(VariantsStruct, Clone, Copy, PartialEq, Debug)]
+#[jump_table]
+pub enum Signed {
+    Below = -5,
+    Zero = 0,
+    Above = 7
+}
+
+#[test]
+fn jump_table_discriminants() {
+    let mut sparse = SparseStruct::new(1, 2, 3, 4);
+    assert_eq!(sparse[Sparse::Low], 1);
+    assert_eq!(sparse[Sparse::Lowest], 2);
+    sparse[Sparse::Higher] += 10;
+    assert_eq!(sparse.values, [1, 2, 3, 14]);
+    assert_eq!(sparse.get(&Sparse::High), Some(&3));
+
+    let signed = SignedStruct::new("below", "zero", "above");
+    assert_eq!(signed[Signed::Below], "below");
+    assert_eq!(signed[Signed::Above], "above");
+    assert_eq!(signed.get_unchecked(&Signed::Zero), &"zero");
+}
+
+// Field order
+
+#[derive(VariantsStruct, Debug, PartialEq)]
+#[struct_derive(Serialize)]
+#[field_order(alphabetical)]
+#[getter_table]
+pub enum Sorted {
+    Zulu,
+    Alpha,
+    Mike(u8)
+}
+
+#[derive(VariantsStruct, Clone, Copy, Debug, PartialEq)]
+#[field_order(Third, First, Second)]
+#[jump_table]
+pub enum Frozen {
+    First,
+    Second,
+    Third
+}
+
+#[test]
+fn field_order() {
+    let sorted = SortedStruct::new(1, 2);
+    assert_eq!((sorted.alpha, sorted.zulu), (1, 2));
+    assert_eq!(serde_json::to_string(&sorted).unwrap(), r#"{"alpha":1,"mike":{},"zulu":2}"#);
+    let order: Vec<_> = sorted.iter().map(|(var, _)| var).collect();
+    assert_eq!(order, vec![Sorted::Alpha, Sorted::Zulu]);
+
+    let frozen = FrozenStruct::new(3, 1, 2);
+    assert_eq!(frozen.values, [3, 1, 2]);
+    assert_eq!(frozen[Frozen::First], 1);
+    assert_eq!(frozen.to_vec(), vec![(Frozen::Third, 3), (Frozen::First, 1), (Frozen::Second, 2)]);
+}
+
+// Cells
+
+#[derive(VariantsStruct)]
+#[struct_derive(Clone, Default, Debug, PartialEq)]
+#[cell = "FlagCell"]
+pub enum Flag {
+    Verbose,
+    Named(&'static str)
+}
+
+#[test]
+fn cell() {
+    let cell = std::sync::Arc::new(FlagCell::default());
+    let threads: Vec<_> = (0..4).map(|_| {
+        let cell = cell.clone();
+        std::thread::spawn(move || for _ in 0..100 {
+            cell.rcu(|current| {
+                let mut next = current.clone();
+                next.verbose += 1;
+                next
+            });
+        })
+    }).collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(cell.load().verbose, 400);
+
+    let mut next = FlagStruct::new(1);
+    next.named_insert("x", 2);
+    let old = cell.swap(next.clone());
+    assert_eq!(old.verbose, 400);
+    assert_eq!(*cell.load(), next);
+    cell.store(FlagStruct::new(3));
+    assert_eq!(std::sync::Arc::try_unwrap(cell).ok().unwrap().into_inner().verbose, 3);
+}
+
+// Comparing different value types
+
+#[derive(VariantsStruct, Debug)]
+#[struct_derive(Debug, Eq)]
+#[cross_eq]
+pub enum Column {
+    Id,
+    Named(&'static str),
+    #[dense_key(max = 4)] Indexed(u8),
+    #[multi] Tagged(u8)
+}
+
+#[derive(VariantsStruct)]
+#[cross_eq]
+pub enum Nothing {}
+
+#[test]
+fn cross_eq() {
+    let mut expected = ColumnStruct::new("id");
+    expected.named_insert("a", "x");
+    expected.indexed_insert(1, "y");
+    expected.push(Column::Tagged(2), "z");
+    let mut actual = ColumnStruct::new(String::from("id"));
+    actual.named_insert("a", String::from("x"));
+    actual.indexed_insert(1, String::from("y"));
+    actual.push(Column::Tagged(2), String::from("z"));
+    assert!(expected == actual);
+    assert_eq!(actual, actual.snapshot());
+
+    actual.push(Column::Tagged(2), String::from("w"));
+    assert!(expected != actual);
+    actual.tagged.clear();
+    assert!(expected != actual);
+    expected.tagged.clear();
+    expected.indexed_remove(&1);
+    assert!(expected != actual);
+
+    assert!(NothingStruct::<i32>::new() == NothingStruct::<i32>::new());
+}
+
+// Looking up by name
+
+#[derive(VariantsStruct)]
+#[rename_fields(prefix = "cfg_")]
+pub enum Console {
+    Verbose,
+    #[field_name(r#type)] Kind,
+    Named(&'static str)
+}
+
+#[test]
+fn get_by_name() {
+    let mut console = ConsoleStruct::new(1, 2);
+    console.cfg_named_insert("verbose", 3);
+    assert_eq!(console.get_by_name("cfg_verbose"), Some(&1));
+    assert_eq!(console.get_by_name("type"), Some(&2));
+    assert_eq!(console.get_by_name("verbose"), None);
+    assert_eq!(console.get_by_name("cfg_named"), None);
+    *console.get_by_name_mut("type").unwrap() = 4;
+    assert_eq!(console.r#type, 4);
+
+    let mut jump = InstructionStruct::new(0, 0, 0, 0);
+    *jump.get_by_name_mut("store").unwrap() = 5;
+    assert_eq!(jump[Instruction::Store], 5);
+}
+
+// Visitors
+
+#[derive(VariantsStruct, PartialEq, Eq, Hash, Debug)]
+#[merge_with(Port)]
+#[catch_all]
+#[visitor]
+#[non_exhaustive]
+pub enum Visited {
+    Plain,
+    Keyed(&'static str),
+    #[dense_key(keys(1, 3))] Dense(u8),
+    #[multi] Many(u8)
+}
+
+#[derive(Default)]
+struct Recorder(Vec<String>);
+
+impl VisitedStructVisitor<i32> for Recorder {
+    fn visit_plain(&mut self, value: &i32) {
+        self.0.push(format!("plain {}", value));
+    }
+
+    fn visit_keyed(&mut self, key: &&'static str, value: &i32) {
+        self.0.push(format!("keyed {} {}", key, value));
+    }
+
+    fn visit_dense(&mut self, key: &u8, value: &i32) {
+        self.0.push(format!("dense {} {}", key, value));
+    }
+
+    fn visit_many(&mut self, key: &u8, value: &i32) {
+        self.0.push(format!("many {} {}", key, value));
+    }
+
+    fn visit_port(&mut self, value: &PortStruct<i32>) {
+        self.0.push(format!("port {}", value.console));
+    }
+
+    fn visit_catch_all(&mut self, var: &Visited, value: &i32) {
+        self.0.push(format!("other {:?} {}", var, value));
+    }
+}
+
+#[test]
+fn visitor() {
+    let mut visited = VisitedStruct::new(1, PortStruct::new(2));
+    visited.keyed_insert("a", 5);
+    visited.dense_insert(3, 6);
+    visited.push(Visited::Many(7), 8);
+    visited.push(Visited::Many(7), 9);
+    visited.catch_all.insert(Visited::Plain, 10);
+
+    let mut recorder = Recorder::default();
+    visited.accept(&mut recorder);
+    assert_eq!(recorder.0, vec![
+        "plain 1", "keyed a 5", "dense 3 6", "many 7 8", "many 7 9", "port 2", "other Plain 10"
+    ]);
+
+    // methods that aren't implemented don't do anything
+    struct Nothing;
+    impl VisitedStructVisitor<i32> for Nothing {}
+    visited.accept(&mut Nothing);
+}
+
+// Named iterators
+
+struct Cursor<'a> {
+    values: HasTuplesStructIter<'a, i32>
+}
+
+impl<'a> Iterator for Cursor<'a> {
+    type Item = i32;
+
+    fn next(&mut self) -> Option<i32> {
+        self.values.next().map(|(_, value)| *value)
+    }
+}
+
+#[test]
+fn named_iterators() {
+    let mut tuple_boi = HasTuplesStruct::new(1);
+    tuple_boi.one_insert("a", 2);
+    let cursor = Cursor { values: tuple_boi.iter() };
+    assert_eq!(cursor.sum::<i32>(), 3);
+
+    let values: HasTuplesStructIntoValues<i32> = tuple_boi.snapshot().into_values();
+    assert_eq!(values.size_hint(), (2, Some(2)));
+    let drain: HasTuplesStructDrain<'_, i32> = tuple_boi.drain();
+    assert_eq!(drain.collect::<Vec<_>>(), vec![(HasTuples::One("a"), 2)]);
+
+    let mut hello: HelloStructIntoValues<i32> = HelloStruct::new(1, 2).into_values();
+    assert_eq!(hello.len(), 2);
+    assert_eq!(hello.next_back(), Some(2));
+
+    let mut observations = ObservationStruct::new(0);
+    observations.push(Observation::Sensor("a"), 1);
+    observations.push(Observation::Sensor("a"), 2);
+    let mut drain = observations.drain();
+    assert_eq!(drain.size_hint(), (0, None));
+    assert_eq!(drain.next(), Some((Observation::Sensor("a"), 1)));
+    assert_eq!(drain.size_hint(), (1, None));
+}
+
+// Boxed fields
+
+#[derive(VariantsStruct, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[struct_derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[boxed_fields]
+#[markers]
+#[getter_table]
+pub enum Buffer {
+    Front,
+    Back,
+    Scratch(u8)
+}
+
+#[derive(VariantsStruct)]
+#[struct_derive(Debug)]
+#[boxed_fields]
+#[cross_eq]
+pub enum Plane {
+    Top,
+    Bottom
+}
+
+#[test]
+fn boxed_fields() {
+    let mut buffers = BufferStruct::new([1u8; 4096], [2; 4096]);
+    let front: &[u8; 4096] = buffers.get_unchecked(&Buffer::Front);
+    assert_eq!(front[0], 1);
+    buffers[Buffer::Back][0] = 3;
+    buffers.push(Buffer::Scratch(1), [4; 4096]);
+    assert_eq!(buffers.get_typed::<buffer::Back>()[0], 3);
+    assert_eq!(buffers.get_by_name("front").map(|values| values[1]), Some(1));
+    assert_eq!(buffers.iter().map(|(_, values)| values[0] as u32).sum::<u32>(), 8);
+
+    let firsts = buffers.map_ref(|values| values[0]);
+    assert_eq!(*firsts.back, 3);
+    let json = serde_json::to_string(&firsts).unwrap();
+    assert_eq!(serde_json::from_str::<BufferStruct<u8>>(&json).unwrap(), firsts);
+    assert_eq!(firsts.map_ref(|value| Some(*value)).transpose(), Some(firsts.clone()));
+    assert_eq!(firsts.clone().into_values().collect::<Vec<_>>(), vec![1, 3, 4]);
+    assert_eq!(firsts.validate_all(|_, value| if *value > 2 { Err(()) } else { Ok(()) }).unwrap_err().len(), 2);
+
+    let mut reset = firsts.clone();
+    reset.reset();
+    assert_eq!(reset, BufferStruct::default());
+    reset.apply_defaults_where(firsts, |value| *value == 0);
+    assert_eq!((*reset.front, *reset.back), (1, 3));
+
+    let columns = PlaneStruct::new(vec![String::from("a"), String::from("b")], vec![String::from("c"), String::from("d")]);
+    let rows = columns.into_rows();
+    assert!(rows[1] == PlaneStruct::new("b", "d"));
+    assert!(rows.into_iter().collect::<PlaneStruct<Vec<String>>>() == PlaneStruct::new(vec!["a", "b"], vec!["c", "d"]));
+}
+
+// Impl attributes
+
+#[derive(VariantsStruct, PartialEq, Eq, Hash, Clone)]
+#[impl_attr(cfg(all()), allow(clippy::needless_lifetimes))]
+#[impl_attr(doc(hidden))]
+#[struct_derive(Debug, PartialEq)]
+pub enum Gated {
+    Always,
+    Keyed(u8)
+}
+
+#[test]
+fn impl_attr() {
+    let mut gated = GatedStruct::new(1);
+    gated.keyed_insert(2, 3);
+    assert_eq!(gated.get(&Gated::Keyed(2)), Some(&3));
+    assert_eq!(gated.iter().count(), 2);
+    assert_eq!(gated.snapshot(), gated);
+}
+
+// Ordered maps
+
+#[derive(VariantsStruct, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[struct_derive(Debug, Serialize)]
+#[ordered_maps]
+pub enum Golden {
+    Header,
+    Line(u32),
+    #[multi] Note(&'static str)
+}
+
+#[derive(VariantsStruct, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[ordered_maps]
+#[non_exhaustive]
+#[catch_all = "other"]
+pub enum Fallback {
+    Known
+}
+
+#[test]
+fn ordered_maps() {
+    let mut golden = GoldenStruct::with_capacity(0, 8);
+    golden.reserve(4);
+    for line in [30, 10, 20] {
+        golden.line_insert(line, line as i32);
+    }
+    golden.push(Golden::Note("b"), 1);
+    golden.push(Golden::Note("a"), 2);
+    golden.push(Golden::Note("a"), 3);
+    golden.shrink_to_fit();
+
+    assert_eq!(golden.iter().map(|(_, value)| *value).collect::<Vec<_>>(), vec![0, 10, 20, 30, 2, 3, 1]);
+    assert_eq!(
+        format!("{:?}", golden),
+        r#"GoldenStruct { header: 0, line: {10: 10, 20: 20, 30: 30}, note: {"a": [2, 3], "b": [1]} }"#
+    );
+    assert_eq!(serde_json::to_string(&golden).unwrap(), r#"{"header":0,"line":{"10":10,"20":20,"30":30},"note":{"a":[2,3],"b":[1]}}"#);
+    assert_eq!(golden.line_key_value(&20), Some((&20, &20)));
+
+    let drained: Vec<_> = golden.drain().map(|(_, value)| value).collect();
+    assert_eq!(drained, vec![10, 20, 30, 2, 3, 1]);
+    assert!(golden.line.is_empty() && golden.note.is_empty());
+
+    let mut fallback = FallbackStruct::new(0);
+    fallback.other.insert(Fallback::Known, 1);
+    assert_eq!(fallback.drain().collect::<Vec<_>>(), vec![(Fallback::Known, 1)]);
+}
+
+// Heap usage
+
+#[test]
+fn heap_usage() {
+    use std::mem::size_of;
+
+    let mut cached: CachedStruct<String> = CachedStruct::new(String::from("hits"), String::new());
+    assert_eq!(cached.heap_usage(), cached.slot.capacity() * size_of::<Option<String>>());
+    cached.entry.insert(String::from("key"), String::from("value"));
+    cached.push(Cached::History(1), String::from("first"));
+    cached.slot[1] = Some(String::from("slot"));
+    let expected = cached.entry.capacity() * size_of::<(String, String)>()
+        + cached.history.capacity() * size_of::<(u8, Vec<String>)>()
+        + cached.history[&1].capacity() * size_of::<String>()
+        + cached.slot.capacity() * size_of::<Option<String>>()
+        + cached.other.capacity() * size_of::<(Cached, String)>();
+    assert_eq!(cached.heap_usage(), expected);
+    assert_eq!(cached.heap_usage_with(String::capacity), expected + cached.hits.capacity() + "value".len() + "first".len() + "slot".len());
+
+    let buffers = BufferStruct::new([0u8; 16], [0; 16]);
+    assert_eq!(buffers.heap_usage(), 2 * 16 + buffers.scratch.capacity() * size_of::<(u8, [u8; 16])>());
+    let merged = MergedStruct::new(1, Directions::new(2, 3), PortStruct::new(4));
+    assert_eq!(merged.heap_usage_with(|_| 1), 3 + merged.port.heap_usage_with(|_| 1));
+}
+
+// Concrete-type impls
+
+#[derive(VariantsStruct, Clone, PartialEq, Debug)]
+#[struct_derive(Debug, PartialEq)]
+#[impl_for(f32, extras(sum, lerp))]
+#[impl_for(u8)]
+pub enum Mix {
+    Dry,
+    Wet,
+    Send(u8)
+}
+
+#[test]
+fn impl_for() {
+    let mut start: MixStruct<f32> = MixStruct::new(1.0, 0.0);
+    start.send.insert(1, 0.5);
+    start.send.insert(2, 2.0);
+    assert_eq!(start.sum(), 3.5);
+
+    let mut end = MixStruct::new(0.0, 1.0);
+    end.send.insert(1, 1.5);
+    let mut expected = MixStruct::new(0.75, 0.25);
+    expected.send.insert(1, 0.75);
+    expected.send.insert(2, 2.0);
+    assert_eq!(start.lerp(&end, 0.25), expected);
+
+    let mut levels: MixStruct<u8> = MixStruct::new(2, 3);
+    assert_eq!((levels.sum(), levels.product(), levels.min(), levels.max()), (5, 6, Some(&2), Some(&3)));
+    levels.send.insert(0, 1);
+    assert_eq!((levels.min(), levels.lerp(&MixStruct::new(4, 3), 1).dry), (Some(&1), 4));
+}
+
+// Getter tables
+
+#[test]
+fn getters() {
+    let mut cycles = InstructionStruct::new(1, 3, 3, 2);
+    let index = InstructionStruct::<i32>::unit_index(&Instruction::Jump).unwrap();
+    *InstructionStruct::GETTERS_MUT[index](&mut cycles) += 1;
+    assert_eq!(InstructionStruct::GETTERS.iter().map(|getter| *getter(&cycles)).collect::<Vec<_>>(), vec![1, 3, 3, 3]);
+
+    let mut buffers = BufferStruct::new([0u8; 2], [1; 2]);
+    let back = BufferStruct::<[u8; 2]>::unit_index(&Buffer::Back).unwrap();
+    BufferStruct::GETTERS_MUT[back](&mut buffers)[0] = 5;
+    assert_eq!(*buffers.back, [5, 1]);
+    assert_eq!(BufferStruct::<[u8; 2]>::unit_index(&Buffer::Scratch(0)), None);
+
+    let sorted = SortedStruct::new(1, 2);
+    let unit: Vec<_> = sorted.iter_unit().map(|(_, value)| *value).collect();
+    assert_eq!(SortedStruct::GETTERS.iter().map(|getter| *getter(&sorted)).collect::<Vec<_>>(), unit);
+    for (var, value) in sorted.iter_unit() {
+        assert_eq!(SortedStruct::GETTERS[SortedStruct::<i32>::unit_index(&var).unwrap()](&sorted), value);
+    }
+}
+
+// Bound syntax
+
+#[derive(VariantsStruct, Clone, PartialEq, Eq, Hash, Debug)]
+#[struct_bounds('static + Clone, for<'a> PartialEq<&'a str>)]
+#[struct_derive(Debug)]
+#[catch_all]
+pub enum Label {
+    Title,
+    Line(u8),
+    #[dense_key(max = 2)] Slot(u8),
+    #[multi] Note(u8)
+}
+
+#[test]
+fn struct_bounds() {
+    let mut labels = LabelStruct::new(String::from("title"));
+    labels.line.insert(1, String::from("first"));
+    labels.push(Label::Slot(1), String::from("slot"));
+    labels.push(Label::Note(0), String::from("note"));
+    assert!(labels[&Label::Title] == "title");
+    assert!(labels[Label::Line(1)] == "first");
+    assert!(labels.map_ref(|value| value.to_uppercase()).line[&1] == "FIRST");
+    assert_eq!(labels.iter().filter(|(_, value)| **value == "note").count(), 1);
+}
+
+// Prefilled keys
+
+const LANGUAGES: [&str; 2] = ["en", "fr"];
+
+#[derive(VariantsStruct, Clone, PartialEq, Eq, Hash, Debug)]
+#[struct_derive(Debug, PartialEq, Default)]
+pub enum Localized {
+    Name,
+    #[prefill_keys(keys = LANGUAGES)] Greeting(&'static str),
+    #[prefill_keys(1, 3, value = T::default())] #[dense_key(max = 4)] Slot(u8),
+    #[prefill_keys(0)] #[multi] Alias(u8)
+}
+
+#[derive(VariantsStruct, Clone, PartialEq, Eq, Hash, Debug)]
+#[struct_bounds(From<&'static str>)]
+pub enum Seeded {
+    Fallback,
+    #[prefill_keys("en", "de", value = T::from(key))] Label(&'static str)
+}
+
+#[test]
+fn prefill_keys() {
+    let localized: LocalizedStruct<String> = LocalizedStruct::new(String::from("name"));
+    assert_eq!(localized.greeting.len(), 2);
+    assert_eq!(localized.get(&Localized::Greeting("fr")), Some(&String::new()));
+    assert_eq!(localized.slot, vec![None, Some(String::new()), None, Some(String::new())]);
+    assert_eq!(localized.get_all(&Localized::Alias(0)), &[String::new()]);
+    assert_eq!(LocalizedStruct::default(), LocalizedStruct::new(String::new()));
+    assert_eq!(LocalizedStruct::<u8>::with_capacity(0, 8).greeting.len(), 2);
+
+    struct NoDefault(usize);
+    impl From<&str> for NoDefault {
+        fn from(key: &str) -> Self {
+            NoDefault(key.len())
+        }
+    }
+    let seeded = SeededStruct::new(String::from("hello"));
+    assert_eq!(seeded.label["de"], "de");
+    let seeded = SeededStruct::with_capacity(NoDefault(0), 4);
+    assert_eq!(seeded.label["en"].0, 2);
+}
+
+#[derive(VariantsStruct, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Debug)]
+#[struct_derive(PartialEq, Debug)]
+#[serde_pairs]
+pub enum Subtitle {
+    Title,
+    #[prefill_keys(1, 2)] Line(u8)
+}
+
+#[test]
+fn prefill_keys_serde_pairs() {
+    let mut start = SubtitleStruct::new(String::from("title"));
+    start.line.remove(&2);
+    start.line_insert(1, String::from("first"));
+
+    let string = serde_json::to_string(&start).unwrap();
+    assert_eq!(string, r#"[["Title","title"],[{"Line":1},"first"]]"#);
+    let end: SubtitleStruct<String> = serde_json::from_str(&string).unwrap();
+    assert_eq!(start, end);
+
+    // deserializing doesn't fill in the keys, so it doesn't need `T: Default`
+    let end: SubtitleStruct<NoDefault> = serde_json::from_str(r#"[["Title",0]]"#).unwrap();
+    assert!(end.line.is_empty());
+}